@@ -0,0 +1,495 @@
+//! ALTER TABLE and table rebuild.
+
+use crate::ddl::{execute, Column, CreateTable, ToSql};
+use crate::error::{Error, ErrorCode};
+use crate::executor::Executor;
+use crate::transaction::{Behavior, Transaction};
+use crate::{quote, require_sqlite, Sqlite3Builder};
+
+/// Change of ALTER TABLE
+#[derive(Clone, Debug)]
+enum Change {
+    RenameTo(String),
+    RenameColumn(String, String),
+    AddColumn(Column),
+    DropColumn(String),
+}
+
+/// ALTER TABLE statements.
+/// Every change is rendered as separate statement, renaming applies to following changes.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::{Column, Sqlite3Builder};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let sql = Sqlite3Builder::alter_table("books")
+///     .add_column(&Column::new("isbn", "TEXT"))
+///     .rename_column("price", "cost")
+///     .rename_to("volumes")
+///     .drop_column("comment")
+///     .sql()?;
+///
+/// assert_eq!("ALTER TABLE books ADD COLUMN isbn TEXT; \
+///     ALTER TABLE books RENAME COLUMN price TO cost; \
+///     ALTER TABLE books RENAME TO volumes; \
+///     ALTER TABLE volumes DROP COLUMN comment;", &sql);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct AlterTable {
+    name: String,
+    changes: Vec<Change>,
+}
+
+impl AlterTable {
+    /// Create statements for table
+    pub fn new<S: ToString>(name: S) -> Self {
+        Self {
+            name: name.to_string(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Rename table
+    pub fn rename_to<S: ToString>(&mut self, name: S) -> &mut Self {
+        self.changes.push(Change::RenameTo(name.to_string()));
+        self
+    }
+
+    /// Rename column.
+    /// Requires SQLite 3.25.0 or later.
+    pub fn rename_column<S: ToString, T: ToString>(&mut self, column: S, name: T) -> &mut Self {
+        self.changes
+            .push(Change::RenameColumn(column.to_string(), name.to_string()));
+        self
+    }
+
+    /// Add column.
+    /// It can't be PRIMARY KEY or UNIQUE and needs non-NULL DEFAULT if it is NOT NULL.
+    pub fn add_column(&mut self, column: &Column) -> &mut Self {
+        self.changes.push(Change::AddColumn(column.clone()));
+        self
+    }
+
+    /// Drop column.
+    /// Requires SQLite 3.35.0 or later.
+    pub fn drop_column<S: ToString>(&mut self, column: S) -> &mut Self {
+        self.changes.push(Change::DropColumn(column.to_string()));
+        self
+    }
+
+    /// Build SQL commands
+    pub fn sql(&self) -> Result<String, Error> {
+        Ok(self.statements()?.join(" "))
+    }
+
+    /// Statement of every change
+    fn statements(&self) -> Result<Vec<String>, Error> {
+        if self.changes.is_empty() {
+            return Err(Error::Build(format!("No changes of table {}", self.name)));
+        }
+        let mut table = self.name.clone();
+        let mut statements = Vec::new();
+        for change in &self.changes {
+            let action = match change {
+                Change::RenameTo(name) => {
+                    let action = format!("RENAME TO {}", name);
+                    statements.push(format!("ALTER TABLE {} {};", table, action));
+                    table = name.clone();
+                    continue;
+                }
+                Change::RenameColumn(column, name) => {
+                    require_sqlite(3_025_000, "RENAME COLUMN")?;
+                    format!("RENAME COLUMN {} TO {}", column, name)
+                }
+                Change::AddColumn(column) => format!("ADD COLUMN {}", column),
+                Change::DropColumn(column) => {
+                    require_sqlite(3_035_000, "DROP COLUMN")?;
+                    format!("DROP COLUMN {}", column)
+                }
+            };
+            statements.push(format!("ALTER TABLE {} {};", table, action));
+        }
+        Ok(statements)
+    }
+
+    /// Execute statements in savepoint, so all changes are applied or none.
+    /// Savepoint starts transaction or nests in the current one.
+    pub fn exec(&self, conn: &impl Executor) -> Result<(), Error> {
+        let statements = self.statements()?;
+        execute(conn, "SAVEPOINT alter_table;")?;
+        for sql in &statements {
+            if let Err(err) = execute(conn, sql) {
+                execute(conn, "ROLLBACK TO alter_table; RELEASE alter_table;")?;
+                return Err(err);
+            }
+        }
+        execute(conn, "RELEASE alter_table;")
+    }
+}
+
+impl ToSql for AlterTable {
+    fn to_sql(&self) -> Result<String, Error> {
+        self.sql()
+    }
+}
+
+/// Rebuild of table for changes ALTER TABLE can't do:
+/// create new table, copy rows, drop old table, rename new one and recreate indexes and triggers.
+/// Runs in transaction with foreign keys disabled and checks them before commit,
+/// so it must not be executed inside another transaction.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::{Column, Sqlite3Builder};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let conn = sqlite3::open(":memory:")?;
+/// conn.execute("CREATE TABLE books (title TEXT, price TEXT); \
+///     CREATE INDEX idx_books_title ON books (title); \
+///     INSERT INTO books VALUES ('Dune', '120');")?;
+///
+/// let mut books = Sqlite3Builder::create_table("books");
+/// books
+///     .column(Column::new("title", "TEXT").not_null())
+///     .column(Column::new("price", "INTEGER").default(0));
+///
+/// Sqlite3Builder::rebuild_table(&books).exec(&conn)?;
+///
+/// let price = Sqlite3Builder::select_from("books").field("price").get_i64(&conn)?;
+/// assert_eq!(120, price);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct TableRebuild {
+    table: CreateTable,
+    copies: Vec<(String, String)>,
+}
+
+impl TableRebuild {
+    /// Create rebuild to new definition of table
+    pub fn new(table: &CreateTable) -> Self {
+        Self {
+            table: table.clone(),
+            copies: Vec::new(),
+        }
+    }
+
+    /// Fill column of new table with expression over old row.
+    /// Columns without expression are copied if old table has them.
+    pub fn copy<S: ToString, T: ToString>(&mut self, column: S, expr: T) -> &mut Self {
+        self.copies.push((column.to_string(), expr.to_string()));
+        self
+    }
+
+    /// Statements run inside transaction
+    fn statements(&self, conn: &impl Executor) -> Result<Vec<String>, Error> {
+        let name = self.table.name();
+        let new_name = new_table_name(name);
+
+        let mut db = Sqlite3Builder::select_from(format!("pragma_table_info({})", quote(name)));
+        let old_columns = db.field("name").get_as::<(String,)>(conn)?;
+        if old_columns.is_empty() {
+            return Err(Error::Build(format!("No such table: {}", name)));
+        }
+
+        let mut columns = Vec::new();
+        let mut values = Vec::new();
+        for column in self
+            .table
+            .columns()
+            .iter()
+            .filter(|col| !col.is_generated())
+        {
+            let copy = self.copies.iter().find(|(col, _)| col == column.name());
+            if let Some((_, expr)) = copy {
+                columns.push(column.name().to_string());
+                values.push(expr.clone());
+            } else if old_columns.iter().any(|(old,)| old == column.name()) {
+                columns.push(column.name().to_string());
+                values.push(column.name().to_string());
+            }
+        }
+
+        let mut db = Sqlite3Builder::select_from("sqlite_master");
+        let table = db.param(name);
+        let objects = db
+            .field("sql")
+            .and_where_eq("tbl_name", &table)
+            .and_where("type IN ('index', 'trigger')")
+            .and_where_is_not_null("sql")
+            .order_asc("rowid")
+            .get_as::<(String,)>(conn)?;
+
+        let mut statements = rebuild_statements(
+            name,
+            &new_name,
+            self.table.renamed(&new_name).sql()?,
+            &columns,
+            &values,
+            legacy_alter_table(conn)?,
+        );
+        statements.extend(objects.into_iter().map(|(sql,)| format!("{};", sql)));
+        Ok(statements)
+    }
+
+    /// Build whole script for review, reading old table from connection
+    pub fn script(&self, conn: &impl Executor) -> Result<String, Error> {
+        let mut script = vec![
+            "PRAGMA foreign_keys = OFF;".to_string(),
+            "BEGIN;".to_string(),
+        ];
+        script.extend(self.statements(conn)?);
+        script.push("PRAGMA foreign_key_check;".to_string());
+        script.push("COMMIT;".to_string());
+        script.push(format!(
+            "PRAGMA foreign_keys = {};",
+            on_off(foreign_keys(conn)?)
+        ));
+        Ok(script.join("\n"))
+    }
+
+    /// Execute rebuild
+    pub fn exec(&self, conn: &impl Executor) -> Result<(), Error> {
+        run_rebuild(conn, |tx| self.statements(tx))
+    }
+}
+
+/// Temporary name of new table while rebuilding
+pub(crate) fn new_table_name(name: &str) -> String {
+    format!("{}_new", name)
+}
+
+/// Current value of foreign_keys pragma
+fn foreign_keys(conn: &impl Executor) -> Result<bool, Error> {
+    Sqlite3Builder::select_from("pragma_foreign_keys")
+        .field("foreign_keys")
+        .get_bool(conn)
+}
+
+/// Value of boolean pragma
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "ON"
+    } else {
+        "OFF"
+    }
+}
+
+/// Current value of legacy_alter_table pragma
+fn legacy_alter_table(conn: &impl Executor) -> Result<bool, Error> {
+    Sqlite3Builder::select_from("pragma_legacy_alter_table")
+        .field("legacy_alter_table")
+        .get_bool(conn)
+}
+
+/// Statements creating new table, copying columns, dropping old table and renaming new one.
+/// Legacy renaming doesn't check views and triggers referring to dropped table,
+/// pragma is set back to its previous value after it.
+pub(crate) fn rebuild_statements(
+    name: &str,
+    new_name: &str,
+    create: String,
+    columns: &[String],
+    values: &[String],
+    legacy: bool,
+) -> Vec<String> {
+    let mut statements = vec![create];
+    if !columns.is_empty() {
+        statements.push(format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {};",
+            new_name,
+            columns.join(", "),
+            values.join(", "),
+            name
+        ));
+    }
+    statements.push(format!("DROP TABLE {};", name));
+    statements.push("PRAGMA legacy_alter_table = ON;".to_string());
+    statements.push(format!("ALTER TABLE {} RENAME TO {};", new_name, name));
+    if !legacy {
+        statements.push("PRAGMA legacy_alter_table = OFF;".to_string());
+    }
+    statements
+}
+
+/// Run rebuild statements in transaction with foreign keys disabled and check them before commit.
+/// Pragmas foreign_keys and legacy_alter_table, which rollback doesn't undo, are restored on error too.
+pub(crate) fn run_rebuild<E, F>(conn: &E, statements: F) -> Result<(), Error>
+where
+    E: Executor,
+    F: FnOnce(&Transaction) -> Result<Vec<String>, Error>,
+{
+    let foreign_keys = foreign_keys(conn)?;
+    let legacy = legacy_alter_table(conn)?;
+    execute(conn, "PRAGMA foreign_keys = OFF;")?;
+    let res = rebuild(conn, statements);
+    let restore = format!(
+        "PRAGMA legacy_alter_table = {}; PRAGMA foreign_keys = {};",
+        on_off(legacy),
+        on_off(foreign_keys)
+    );
+    execute(conn, &restore)?;
+    res
+}
+
+/// Rebuild in transaction
+fn rebuild<E, F>(conn: &E, statements: F) -> Result<(), Error>
+where
+    E: Executor,
+    F: FnOnce(&Transaction) -> Result<Vec<String>, Error>,
+{
+    let tx = Transaction::with_behavior(conn, Behavior::Immediate)?;
+    for sql in statements(&tx)? {
+        execute(&tx, &sql)?;
+    }
+    let violations = Sqlite3Builder::select_from("pragma_foreign_key_check")
+        .field("COUNT(*)")
+        .get_i64(&tx)?;
+    if violations > 0 {
+        return Err(Error::Sqlite {
+            code: Some(ErrorCode::Constraint),
+            message: format!("FOREIGN KEY constraint failed in {} rows", violations),
+        });
+    }
+    tx.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alter_table() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+        conn.execute("CREATE TABLE books (title TEXT, price INTEGER, comment TEXT);")?;
+
+        Sqlite3Builder::alter_table("books")
+            .add_column(Column::new("isbn", "TEXT").default("''"))
+            .rename_column("price", "cost")
+            .drop_column("comment")
+            .rename_to("volumes")
+            .exec(&conn)?;
+
+        let columns = Sqlite3Builder::select_from("pragma_table_info('volumes')")
+            .field("name")
+            .get_as::<(String,)>(&conn)?;
+        assert_eq!(
+            columns,
+            vec![
+                ("title".to_string(),),
+                ("cost".to_string(),),
+                ("isbn".to_string(),),
+            ]
+        );
+
+        let res = Sqlite3Builder::alter_table("volumes")
+            .rename_column("cost", "price")
+            .drop_column("comment")
+            .exec(&conn);
+        assert!(res.is_err());
+        let columns = Sqlite3Builder::select_from("pragma_table_info('volumes')")
+            .field("name")
+            .and_where_eq("name", "'cost'")
+            .get_as::<(String,)>(&conn)?;
+        assert_eq!(columns.len(), 1);
+
+        let err = Sqlite3Builder::alter_table("volumes").sql().unwrap_err();
+        assert_eq!(err.to_string(), "No changes of table volumes");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_rebuild() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+        conn.execute(
+            "PRAGMA foreign_keys = ON; \
+             CREATE TABLE authors (id INTEGER PRIMARY KEY, name TEXT); \
+             CREATE TABLE books (title TEXT, author INTEGER REFERENCES authors (id), price TEXT); \
+             CREATE UNIQUE INDEX idx_books_title ON books (title); \
+             CREATE TRIGGER trg_books AFTER DELETE ON books BEGIN DELETE FROM authors; END; \
+             INSERT INTO authors VALUES (1, 'Herbert'); \
+             CREATE VIEW titles AS SELECT title FROM books; \
+             INSERT INTO books VALUES ('Dune', 1, '9.99');",
+        )?;
+
+        let mut books = Sqlite3Builder::create_table("books");
+        books
+            .column(Column::new("title", "TEXT").not_null())
+            .column(
+                Column::new("author", "INTEGER")
+                    .references(&crate::ForeignKey::new("authors", &["id"])),
+            )
+            .column(Column::new("cents", "INTEGER").not_null())
+            .strict();
+
+        let mut rebuild = Sqlite3Builder::rebuild_table(&books);
+        rebuild.copy("cents", "CAST(price * 100 AS INTEGER)");
+
+        assert_eq!(
+            rebuild.script(&conn)?,
+            "PRAGMA foreign_keys = OFF;\n\
+             BEGIN;\n\
+             CREATE TABLE books_new (title TEXT NOT NULL, \
+             author INTEGER REFERENCES authors (id), cents INTEGER NOT NULL) STRICT;\n\
+             INSERT INTO books_new (title, author, cents) \
+             SELECT title, author, CAST(price * 100 AS INTEGER) FROM books;\n\
+             DROP TABLE books;\n\
+             PRAGMA legacy_alter_table = ON;\n\
+             ALTER TABLE books_new RENAME TO books;\n\
+             PRAGMA legacy_alter_table = OFF;\n\
+             CREATE UNIQUE INDEX idx_books_title ON books (title);\n\
+             CREATE TRIGGER trg_books AFTER DELETE ON books BEGIN DELETE FROM authors; END;\n\
+             PRAGMA foreign_key_check;\n\
+             COMMIT;\n\
+             PRAGMA foreign_keys = ON;"
+        );
+
+        rebuild.exec(&conn)?;
+
+        let cents = Sqlite3Builder::select_from("books")
+            .field("cents")
+            .get_i64(&conn)?;
+        assert_eq!(cents, 999);
+        let titles = Sqlite3Builder::select_from("titles")
+            .field("title")
+            .get_string(&conn)?;
+        assert_eq!(titles, "Dune");
+        let foreign_keys = Sqlite3Builder::select_from("pragma_foreign_keys")
+            .field("foreign_keys")
+            .get_bool(&conn)?;
+        assert!(foreign_keys);
+
+        conn.execute(
+            "PRAGMA foreign_keys = OFF; UPDATE books SET author = 2; PRAGMA foreign_keys = ON;",
+        )?;
+        let mut bad = Sqlite3Builder::rebuild_table(&books);
+        let err = bad.copy("author", "author").exec(&conn).unwrap_err();
+        assert!(err.is_constraint());
+        assert!(!legacy_alter_table(&conn)?);
+        let cents = Sqlite3Builder::select_from("books")
+            .field("cents")
+            .get_i64(&conn)?;
+        assert_eq!(cents, 999);
+
+        conn.execute("UPDATE books SET author = 1; PRAGMA legacy_alter_table = ON;")?;
+        let rebuild = Sqlite3Builder::rebuild_table(&books);
+        assert!(!rebuild.script(&conn)?.contains("legacy_alter_table = OFF"));
+        conn.execute("PRAGMA foreign_keys = OFF;")?;
+        assert!(rebuild
+            .script(&conn)?
+            .ends_with("COMMIT;\nPRAGMA foreign_keys = OFF;"));
+        rebuild.exec(&conn)?;
+        assert!(legacy_alter_table(&conn)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+//! Prepared statement cache.
+
+use crate::error::Error;
+use crate::executor::{ExecResult, Executor};
+use sqlite3::{Connection, State, Statement};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+/// Default number of cached statements
+pub const DEFAULT_CACHE_CAPACITY: usize = 16;
+
+/// Connection with LRU cache of prepared statements keyed by SQL text.
+/// Cache is cleared when database schema changes.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::{CachedConnection, Sqlite3Builder};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let conn = sqlite3::open(":memory:")?;
+/// let cached = CachedConnection::with_capacity(&conn, 32);
+///
+/// let mut db = Sqlite3Builder::select_from("sqlite_master");
+/// db.field("COUNT(*)");
+///
+/// for _ in 0..3 {
+///     assert_eq!(0, db.get_i64(&cached)?);
+/// }
+///
+/// assert_eq!(cached.misses(), 1);
+/// assert_eq!(cached.hits(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CachedConnection<'c> {
+    conn: &'c Connection,
+    capacity: Cell<usize>,
+    statements: RefCell<VecDeque<(String, Statement<'c>)>>,
+    schema: RefCell<Option<Statement<'c>>>,
+    counters: RefCell<Option<Statement<'c>>>,
+    schema_version: Cell<i64>,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+}
+
+impl<'c> CachedConnection<'c> {
+    /// Wrap connection with cache of default capacity
+    pub fn new(conn: &'c impl Executor) -> Self {
+        Self::with_capacity(conn, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Wrap connection with cache of given capacity.
+    /// Zero capacity disables caching.
+    pub fn with_capacity(conn: &'c impl Executor, capacity: usize) -> Self {
+        Self {
+            conn: conn.connection(),
+            capacity: Cell::new(capacity),
+            statements: RefCell::new(VecDeque::with_capacity(capacity)),
+            schema: RefCell::new(None),
+            counters: RefCell::new(None),
+            schema_version: Cell::new(-1),
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        }
+    }
+
+    /// Maximal number of cached statements
+    pub fn capacity(&self) -> usize {
+        self.capacity.get()
+    }
+
+    /// Change capacity, dropping least recently used statements
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.set(capacity);
+        self.statements.borrow_mut().truncate(capacity);
+    }
+
+    /// Number of cached statements
+    pub fn len(&self) -> usize {
+        self.statements.borrow().len()
+    }
+
+    /// True if no statement is cached
+    pub fn is_empty(&self) -> bool {
+        self.statements.borrow().is_empty()
+    }
+
+    /// Number of statements taken from cache
+    pub fn hits(&self) -> u64 {
+        self.hits.get()
+    }
+
+    /// Number of statements prepared from scratch
+    pub fn misses(&self) -> u64 {
+        self.misses.get()
+    }
+
+    /// Drop all cached statements
+    pub fn clear(&self) {
+        self.statements.borrow_mut().clear();
+    }
+
+    /// Clear cache if schema was changed since last check
+    fn check_schema(&self) -> Result<(), Error> {
+        let mut schema = self.schema.borrow_mut();
+        if schema.is_none() {
+            *schema = Some(self.conn.prepare("PRAGMA schema_version;")?);
+        }
+        let stmt = schema.as_mut().unwrap();
+        let version = match stmt.next()? {
+            State::Row => stmt.read::<i64>(0)?,
+            State::Done => 0,
+        };
+        stmt.reset()?;
+        if version != self.schema_version.replace(version) {
+            self.clear();
+        }
+        Ok(())
+    }
+
+    /// Take statement from cache or prepare new one
+    fn take(&self, sql: &str) -> Result<Statement<'c>, Error> {
+        self.check_schema()?;
+        let mut statements = self.statements.borrow_mut();
+        match statements.iter().position(|(key, _)| key == sql) {
+            Some(idx) => {
+                self.hits.set(self.hits.get() + 1);
+                Ok(statements.remove(idx).unwrap().1)
+            }
+            None => {
+                self.misses.set(self.misses.get() + 1);
+                Ok(self.conn.prepare(sql)?)
+            }
+        }
+    }
+
+    /// Return statement to cache as most recently used
+    fn put(&self, sql: &str, mut stmt: Statement<'c>) {
+        let capacity = self.capacity.get();
+        if capacity == 0 || stmt.reset().is_err() {
+            return;
+        }
+        let mut statements = self.statements.borrow_mut();
+        statements.truncate(capacity - 1);
+        statements.push_front((sql.to_string(), stmt));
+    }
+}
+
+impl<'c> Executor for CachedConnection<'c> {
+    fn connection(&self) -> &Connection {
+        self.conn
+    }
+
+    fn with_statement<T, F>(&self, sql: &str, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Statement) -> Result<T, Error>,
+    {
+        let mut stmt = self.take(sql)?;
+        let result = f(&mut stmt);
+        self.put(sql, stmt);
+        result
+    }
+
+    fn exec_result(&self) -> Result<ExecResult, Error> {
+        let mut counters = self.counters.borrow_mut();
+        if counters.is_none() {
+            *counters = Some(self.conn.prepare(ExecResult::SQL)?);
+        }
+        let stmt = counters.as_mut().unwrap();
+        let result = ExecResult::read(stmt);
+        stmt.reset()?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sqlite3Builder;
+
+    #[test]
+    fn test_statement_cache() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+        conn.execute("CREATE TABLE books (title TEXT);")?;
+        let cached = CachedConnection::with_capacity(&conn, 2);
+
+        let mut count = Sqlite3Builder::select_from("books");
+        count.field("COUNT(*)");
+        let mut insert = Sqlite3Builder::insert_into("books");
+        insert.field("title");
+        let title = insert.param("Dune");
+        insert.values(&[title]);
+
+        insert.exec(&cached)?;
+        assert_eq!(insert.exec(&cached)?.last_insert_rowid(), 2);
+        assert_eq!(count.get_i64(&cached)?, 2);
+        assert_eq!((cached.hits(), cached.misses()), (1, 2));
+        assert_eq!(cached.len(), 2);
+
+        Sqlite3Builder::select_values(&["1"]).exec(&cached)?;
+        assert_eq!(cached.len(), 2);
+        assert_eq!(count.get_i64(&cached)?, 2);
+        assert_eq!((cached.hits(), cached.misses()), (2, 3));
+
+        conn.execute("ALTER TABLE books ADD COLUMN price INTEGER;")?;
+        assert_eq!(count.get_i64(&cached)?, 2);
+        assert_eq!((cached.hits(), cached.misses()), (2, 4));
+        assert_eq!(cached.len(), 1);
+
+        cached.set_capacity(0);
+        assert!(cached.is_empty());
+        assert_eq!(count.get_i64(&cached)?, 2);
+        assert!(cached.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+//! Common table expressions of WITH clause.
+
+use crate::error::Error;
+
+/// Materialization hint of common table expression
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// Let query planner decide
+    Auto,
+    /// AS MATERIALIZED
+    Materialized,
+    /// AS NOT MATERIALIZED
+    NotMaterialized,
+}
+
+/// Named subquery of WITH clause
+#[derive(Clone, Debug)]
+pub struct Cte {
+    name: String,
+    hint: Hint,
+    query: Result<String, String>,
+}
+
+impl Cte {
+    /// Create CTE from name with optional column list and subquery text
+    pub fn new(name: String, hint: Hint, query: Result<String, String>) -> Self {
+        Self { name, hint, query }
+    }
+
+    /// True if CTE has materialization hint
+    pub fn has_hint(&self) -> bool {
+        self.hint != Hint::Auto
+    }
+
+    /// Build `name AS (query)`
+    pub fn sql(&self) -> Result<String, Error> {
+        let query = self.query.as_ref().map_err(|err| {
+            Error::Build(format!("Common table expression {}: {}", self.name, err))
+        })?;
+        let hint = match self.hint {
+            Hint::Auto => "",
+            Hint::Materialized => "MATERIALIZED ",
+            Hint::NotMaterialized => "NOT MATERIALIZED ",
+        };
+        Ok(format!("{} AS {}({})", self.name, hint, query))
+    }
+}
+
+/// Build WITH clause followed by space, or nothing if there are no CTEs
+pub fn with_clause(ctes: &[Cte], recursive: bool) -> Result<String, Error> {
+    if ctes.is_empty() {
+        return Ok(String::new());
+    }
+    let ctes = ctes.iter().map(Cte::sql).collect::<Result<Vec<_>, _>>()?;
+    let recursive = if recursive { "RECURSIVE " } else { "" };
+    Ok(format!("WITH {}{} ", recursive, ctes.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_clause() -> Result<(), Error> {
+        assert_eq!(with_clause(&[], true)?, "");
+
+        let ctes = vec![
+            Cte::new(
+                "cheap".to_string(),
+                Hint::Auto,
+                Ok("SELECT * FROM books WHERE price < 100".to_string()),
+            ),
+            Cte::new(
+                "n(x)".to_string(),
+                Hint::NotMaterialized,
+                Ok("SELECT 1".to_string()),
+            ),
+        ];
+
+        assert_eq!(
+            with_clause(&ctes, false)?,
+            "WITH cheap AS (SELECT * FROM books WHERE price < 100), \
+             n(x) AS NOT MATERIALIZED (SELECT 1) "
+        );
+
+        let broken = Cte::new(
+            "broken".to_string(),
+            Hint::Materialized,
+            Err("No table name".to_string()),
+        );
+
+        assert_eq!(
+            with_clause(&[broken], true).unwrap_err().to_string(),
+            "Common table expression broken: No table name"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1244 @@
+//! Schema definition statements.
+
+use crate::error::Error;
+use crate::executor::Executor;
+use crate::{require_sqlite, Sqlite3Builder};
+use std::fmt;
+
+/// Execute DDL statement without caching it
+pub(crate) fn execute(conn: &impl Executor, sql: &str) -> Result<(), Error> {
+    debug!("Exec DDL sql = {}", sql);
+    conn.connection().execute(sql)?;
+    Ok(())
+}
+
+/// Action of foreign key on change of parent row
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FkAction {
+    /// NO ACTION
+    NoAction,
+    /// RESTRICT
+    Restrict,
+    /// SET NULL
+    SetNull,
+    /// SET DEFAULT
+    SetDefault,
+    /// CASCADE
+    Cascade,
+}
+
+impl fmt::Display for FkAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let action = match self {
+            FkAction::NoAction => "NO ACTION",
+            FkAction::Restrict => "RESTRICT",
+            FkAction::SetNull => "SET NULL",
+            FkAction::SetDefault => "SET DEFAULT",
+            FkAction::Cascade => "CASCADE",
+        };
+        write!(f, "{}", action)
+    }
+}
+
+/// Foreign key clause: `REFERENCES parent (columns) ON DELETE ... ON UPDATE ...`
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// use sqlite3builder::{FkAction, ForeignKey};
+///
+/// let fk = ForeignKey::new("authors", &["id"])
+///     .on_delete(FkAction::Cascade)
+///     .to_string();
+///
+/// assert_eq!("REFERENCES authors (id) ON DELETE CASCADE", &fk);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ForeignKey {
+    table: String,
+    columns: Vec<String>,
+    on_delete: Option<FkAction>,
+    on_update: Option<FkAction>,
+    deferred: bool,
+}
+
+impl ForeignKey {
+    /// Reference parent table columns.
+    /// Empty column list refers to primary key of parent table.
+    pub fn new<S: ToString, T: ToString>(table: S, columns: &[T]) -> Self {
+        Self {
+            table: table.to_string(),
+            columns: columns.iter().map(|col| col.to_string()).collect(),
+            on_delete: None,
+            on_update: None,
+            deferred: false,
+        }
+    }
+
+    /// Set ON DELETE action
+    pub fn on_delete(&mut self, action: FkAction) -> &mut Self {
+        self.on_delete = Some(action);
+        self
+    }
+
+    /// Set ON UPDATE action
+    pub fn on_update(&mut self, action: FkAction) -> &mut Self {
+        self.on_update = Some(action);
+        self
+    }
+
+    /// Check constraint at commit: DEFERRABLE INITIALLY DEFERRED
+    pub fn deferred(&mut self) -> &mut Self {
+        self.deferred = true;
+        self
+    }
+}
+
+impl fmt::Display for ForeignKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "REFERENCES {}", self.table)?;
+        if !self.columns.is_empty() {
+            write!(f, " ({})", self.columns.join(", "))?;
+        }
+        if let Some(action) = self.on_delete {
+            write!(f, " ON DELETE {}", action)?;
+        }
+        if let Some(action) = self.on_update {
+            write!(f, " ON UPDATE {}", action)?;
+        }
+        if self.deferred {
+            write!(f, " DEFERRABLE INITIALLY DEFERRED")?;
+        }
+        Ok(())
+    }
+}
+
+/// Column definition of CREATE TABLE
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// use sqlite3builder::{quote, Column};
+///
+/// let column = Column::new("title", "TEXT")
+///     .not_null()
+///     .default(quote("Untitled"))
+///     .collate("NOCASE")
+///     .to_string();
+///
+/// assert_eq!("title TEXT NOT NULL DEFAULT 'Untitled' COLLATE NOCASE", &column);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Column {
+    name: String,
+    kind: String,
+    constraints: Vec<String>,
+    generated: bool,
+}
+
+impl Column {
+    /// Create column with type, empty type is allowed
+    pub fn new<S: ToString, T: ToString>(name: S, kind: T) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            constraints: Vec::new(),
+            generated: false,
+        }
+    }
+
+    /// Column name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn constraint(&mut self, constraint: String) -> &mut Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    /// Add PRIMARY KEY
+    pub fn primary_key(&mut self) -> &mut Self {
+        self.constraint("PRIMARY KEY".to_string())
+    }
+
+    /// Add PRIMARY KEY AUTOINCREMENT, column type must be INTEGER
+    pub fn autoincrement(&mut self) -> &mut Self {
+        self.constraint("PRIMARY KEY AUTOINCREMENT".to_string())
+    }
+
+    /// Add NOT NULL
+    pub fn not_null(&mut self) -> &mut Self {
+        self.constraint("NOT NULL".to_string())
+    }
+
+    /// Add UNIQUE
+    pub fn unique(&mut self) -> &mut Self {
+        self.constraint("UNIQUE".to_string())
+    }
+
+    /// Add DEFAULT, expression in parentheses is allowed.
+    /// Use `quote` for string values.
+    pub fn default<S: ToString>(&mut self, value: S) -> &mut Self {
+        self.constraint(format!("DEFAULT {}", value.to_string()))
+    }
+
+    /// Add CHECK
+    pub fn check<S: ToString>(&mut self, expr: S) -> &mut Self {
+        self.constraint(format!("CHECK ({})", expr.to_string()))
+    }
+
+    /// Add COLLATE
+    pub fn collate<S: ToString>(&mut self, collation: S) -> &mut Self {
+        self.constraint(format!("COLLATE {}", collation.to_string()))
+    }
+
+    /// Add foreign key
+    pub fn references(&mut self, fk: &ForeignKey) -> &mut Self {
+        self.constraint(fk.to_string())
+    }
+
+    /// Make column computed on read: GENERATED ALWAYS AS (expr) VIRTUAL.
+    /// Requires SQLite 3.31.0 or later.
+    pub fn generated_virtual<S: ToString>(&mut self, expr: S) -> &mut Self {
+        self.generated = true;
+        self.constraint(format!(
+            "GENERATED ALWAYS AS ({}) VIRTUAL",
+            expr.to_string()
+        ))
+    }
+
+    /// Make column computed on write: GENERATED ALWAYS AS (expr) STORED.
+    /// Requires SQLite 3.31.0 or later.
+    pub fn generated_stored<S: ToString>(&mut self, expr: S) -> &mut Self {
+        self.generated = true;
+        self.constraint(format!("GENERATED ALWAYS AS ({}) STORED", expr.to_string()))
+    }
+
+    /// True if column is generated
+    pub(crate) fn is_generated(&self) -> bool {
+        self.generated
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.kind.is_empty() {
+            write!(f, " {}", self.kind)?;
+        }
+        for constraint in &self.constraints {
+            write!(f, " {}", constraint)?;
+        }
+        Ok(())
+    }
+}
+
+/// CREATE TABLE statement
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::{Column, FkAction, ForeignKey, Sqlite3Builder};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let sql = Sqlite3Builder::create_table("books")
+///     .if_not_exists()
+///     .column(Column::new("id", "INTEGER").autoincrement())
+///     .column(Column::new("title", "TEXT").not_null())
+///     .column(Column::new("price", "INTEGER").check("price >= 0"))
+///     .column(Column::new("author", "INTEGER")
+///         .references(ForeignKey::new("authors", &["id"]).on_delete(FkAction::SetNull)))
+///     .unique(&["title", "author"])
+///     .sql()?;
+///
+/// assert_eq!("CREATE TABLE IF NOT EXISTS books (\
+///     id INTEGER PRIMARY KEY AUTOINCREMENT, \
+///     title TEXT NOT NULL, \
+///     price INTEGER CHECK (price >= 0), \
+///     author INTEGER REFERENCES authors (id) ON DELETE SET NULL, \
+///     UNIQUE (title, author));", &sql);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CreateTable {
+    name: String,
+    temporary: bool,
+    if_not_exists: bool,
+    columns: Vec<Column>,
+    constraints: Vec<String>,
+    without_rowid: bool,
+    strict: bool,
+}
+
+impl CreateTable {
+    /// Create statement for table
+    pub fn new<S: ToString>(name: S) -> Self {
+        Self {
+            name: name.to_string(),
+            temporary: false,
+            if_not_exists: false,
+            columns: Vec::new(),
+            constraints: Vec::new(),
+            without_rowid: false,
+            strict: false,
+        }
+    }
+
+    /// Table name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Column definitions
+    pub(crate) fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Same definition under other name
+    pub(crate) fn renamed(&self, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// Create TEMPORARY table
+    pub fn temporary(&mut self) -> &mut Self {
+        self.temporary = true;
+        self
+    }
+
+    /// Add IF NOT EXISTS
+    pub fn if_not_exists(&mut self) -> &mut Self {
+        self.if_not_exists = true;
+        self
+    }
+
+    /// Add column
+    pub fn column(&mut self, column: &Column) -> &mut Self {
+        self.columns.push(column.clone());
+        self
+    }
+
+    /// Add table PRIMARY KEY of several columns
+    pub fn primary_key<S: ToString>(&mut self, columns: &[S]) -> &mut Self {
+        let columns = join(columns);
+        self.constraints.push(format!("PRIMARY KEY ({})", columns));
+        self
+    }
+
+    /// Add table UNIQUE constraint of several columns
+    pub fn unique<S: ToString>(&mut self, columns: &[S]) -> &mut Self {
+        let columns = join(columns);
+        self.constraints.push(format!("UNIQUE ({})", columns));
+        self
+    }
+
+    /// Add table CHECK constraint
+    pub fn check<S: ToString>(&mut self, expr: S) -> &mut Self {
+        self.constraints
+            .push(format!("CHECK ({})", expr.to_string()));
+        self
+    }
+
+    /// Add table FOREIGN KEY constraint
+    pub fn foreign_key<S: ToString>(&mut self, columns: &[S], fk: &ForeignKey) -> &mut Self {
+        let columns = join(columns);
+        self.constraints
+            .push(format!("FOREIGN KEY ({}) {}", columns, fk));
+        self
+    }
+
+    /// Add WITHOUT ROWID, table needs PRIMARY KEY
+    pub fn without_rowid(&mut self) -> &mut Self {
+        self.without_rowid = true;
+        self
+    }
+
+    /// Add STRICT to enforce column types.
+    /// Requires SQLite 3.37.0 or later.
+    pub fn strict(&mut self) -> &mut Self {
+        self.strict = true;
+        self
+    }
+
+    /// Build SQL command
+    pub fn sql(&self) -> Result<String, Error> {
+        if self.columns.is_empty() {
+            return Err(Error::Build(format!("No columns in table {}", self.name)));
+        }
+        if self.strict {
+            require_sqlite(3_037_000, "STRICT table")?;
+        }
+        if self.columns.iter().any(Column::is_generated) {
+            require_sqlite(3_031_000, "Generated column")?;
+        }
+
+        let temporary = if self.temporary { " TEMPORARY" } else { "" };
+        let if_not_exists = if self.if_not_exists {
+            " IF NOT EXISTS"
+        } else {
+            ""
+        };
+        let defs = self
+            .columns
+            .iter()
+            .map(Column::to_string)
+            .chain(self.constraints.iter().cloned())
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let mut options = Vec::new();
+        if self.without_rowid {
+            options.push("WITHOUT ROWID");
+        }
+        if self.strict {
+            options.push("STRICT");
+        }
+        let options = if options.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", options.join(", "))
+        };
+
+        Ok(format!(
+            "CREATE{} TABLE{} {} ({}){};",
+            temporary, if_not_exists, self.name, defs, options
+        ))
+    }
+
+    /// Execute statement
+    pub fn exec(&self, conn: &impl Executor) -> Result<(), Error> {
+        execute(conn, &self.sql()?)
+    }
+}
+
+/// CREATE INDEX statement.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::Sqlite3Builder;
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let sql = Sqlite3Builder::create_index("idx_books_title", "books")
+///     .unique()
+///     .if_not_exists()
+///     .column_collate("title", "NOCASE", false)
+///     .column("lower(author)")
+///     .sql()?;
+///
+/// assert_eq!("CREATE UNIQUE INDEX IF NOT EXISTS idx_books_title ON books (title COLLATE NOCASE, lower(author));", &sql);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CreateIndex {
+    name: String,
+    table: String,
+    unique: bool,
+    if_not_exists: bool,
+    columns: Vec<String>,
+    wheres: Result<String, String>,
+}
+
+impl CreateIndex {
+    /// Create statement for index on table
+    pub fn new<S: ToString, T: ToString>(name: S, table: T) -> Self {
+        Self {
+            name: name.to_string(),
+            table: table.to_string(),
+            unique: false,
+            if_not_exists: false,
+            columns: Vec::new(),
+            wheres: Ok(String::new()),
+        }
+    }
+
+    /// Make index UNIQUE
+    pub fn unique(&mut self) -> &mut Self {
+        self.unique = true;
+        self
+    }
+
+    /// Add IF NOT EXISTS
+    pub fn if_not_exists(&mut self) -> &mut Self {
+        self.if_not_exists = true;
+        self
+    }
+
+    /// Add indexed column or expression
+    pub fn column<S: ToString>(&mut self, expr: S) -> &mut Self {
+        self.columns.push(expr.to_string());
+        self
+    }
+
+    /// Add indexed column or expression with sort order
+    pub fn column_order<S: ToString>(&mut self, expr: S, desc: bool) -> &mut Self {
+        if desc {
+            self.column(format!("{} DESC", expr.to_string()))
+        } else {
+            self.column(expr)
+        }
+    }
+
+    /// Add indexed column or expression with collation and sort order
+    pub fn column_collate<S, C>(&mut self, expr: S, collation: C, desc: bool) -> &mut Self
+    where
+        S: ToString,
+        C: ToString,
+    {
+        let column = format!("{} COLLATE {}", expr.to_string(), collation.to_string());
+        self.column_order(column, desc)
+    }
+
+    /// Make partial index of rows selected by WHERE of query over the table.
+    /// Query can't have other clauses or bound parameters.
+    pub fn partial(&mut self, query: &Sqlite3Builder) -> &mut Self {
+        self.wheres = query
+            .where_clause(&self.table)
+            .map_err(|err| err.to_string());
+        self
+    }
+
+    /// Build statement
+    pub fn sql(&self) -> Result<String, Error> {
+        if self.columns.is_empty() {
+            return Err(Error::Build(format!("No columns in index {}", self.name)));
+        }
+        let wheres = self.wheres.clone().map_err(Error::Build)?;
+        let unique = if self.unique { " UNIQUE" } else { "" };
+        let if_not_exists = if self.if_not_exists {
+            " IF NOT EXISTS"
+        } else {
+            ""
+        };
+        Ok(format!(
+            "CREATE{} INDEX{} {} ON {} ({}){};",
+            unique,
+            if_not_exists,
+            self.name,
+            self.table,
+            self.columns.join(", "),
+            wheres
+        ))
+    }
+
+    /// Execute statement
+    pub fn exec(&self, conn: &impl Executor) -> Result<(), Error> {
+        execute(conn, &self.sql()?)
+    }
+}
+
+/// DROP statement of schema object
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::Sqlite3Builder;
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let sql = Sqlite3Builder::drop_index("idx_books_title")
+///     .if_exists()
+///     .sql()?;
+///
+/// assert_eq!("DROP INDEX IF EXISTS idx_books_title;", &sql);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DropStatement {
+    object: &'static str,
+    name: String,
+    if_exists: bool,
+}
+
+impl DropStatement {
+    /// Create statement dropping object of kind: INDEX, TABLE, ...
+    pub(crate) fn new(object: &'static str, name: String) -> Self {
+        Self {
+            object,
+            name,
+            if_exists: false,
+        }
+    }
+
+    /// Add IF EXISTS
+    pub fn if_exists(&mut self) -> &mut Self {
+        self.if_exists = true;
+        self
+    }
+
+    /// Build SQL command
+    pub fn sql(&self) -> Result<String, Error> {
+        if self.name.is_empty() {
+            return Err(Error::Build(format!(
+                "No {} name",
+                self.object.to_lowercase()
+            )));
+        }
+        let if_exists = if self.if_exists { " IF EXISTS" } else { "" };
+        Ok(format!("DROP {}{} {};", self.object, if_exists, self.name))
+    }
+
+    /// Execute statement
+    pub fn exec(&self, conn: &impl Executor) -> Result<(), Error> {
+        execute(conn, &self.sql()?)
+    }
+}
+
+/// Text of statement used inside schema object, which can't have bound parameters
+pub(crate) fn statement_text(query: &Sqlite3Builder, place: &str) -> Result<String, String> {
+    if !query.params().is_empty() {
+        return Err(format!("Parameters are not allowed in {}", place));
+    }
+    query.sql().map_err(|err| err.to_string())
+}
+
+/// Statement rendered to SQL text without bound parameters, e.g. step of migration.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::{Column, Sqlite3Builder, ToSql};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mut books = Sqlite3Builder::create_table("books");
+/// books.column(&Column::new("title", "TEXT"));
+/// let authors = Sqlite3Builder::drop_table("authors");
+/// let steps: [&dyn ToSql; 2] = [&books, &authors];
+/// let sql = steps
+///     .iter()
+///     .map(|step| step.to_sql())
+///     .collect::<Result<Vec<String>, _>>()?;
+///
+/// assert_eq!(sql, ["CREATE TABLE books (title TEXT);", "DROP TABLE authors;"]);
+/// # Ok(())
+/// # }
+/// ```
+pub trait ToSql {
+    /// Build SQL text
+    fn to_sql(&self) -> Result<String, Error>;
+}
+
+impl ToSql for Sqlite3Builder {
+    fn to_sql(&self) -> Result<String, Error> {
+        statement_text(self, "SQL text").map_err(Error::Build)
+    }
+}
+
+impl ToSql for CreateTable {
+    fn to_sql(&self) -> Result<String, Error> {
+        self.sql()
+    }
+}
+
+impl ToSql for CreateIndex {
+    fn to_sql(&self) -> Result<String, Error> {
+        self.sql()
+    }
+}
+
+impl ToSql for DropStatement {
+    fn to_sql(&self) -> Result<String, Error> {
+        self.sql()
+    }
+}
+
+impl ToSql for CreateView {
+    fn to_sql(&self) -> Result<String, Error> {
+        self.sql()
+    }
+}
+
+impl ToSql for CreateTrigger {
+    fn to_sql(&self) -> Result<String, Error> {
+        self.sql()
+    }
+}
+
+/// CREATE VIEW statement.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::Sqlite3Builder;
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let sql = Sqlite3Builder::create_view(
+///     "cheap_books",
+///     Sqlite3Builder::select_from("books")
+///         .field("title")
+///         .and_where_lt("price", 100),
+/// )
+/// .if_not_exists()
+/// .sql()?;
+///
+/// assert_eq!("CREATE VIEW IF NOT EXISTS cheap_books AS SELECT title FROM books WHERE price < 100;", &sql);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CreateView {
+    name: String,
+    temporary: bool,
+    if_not_exists: bool,
+    columns: Vec<String>,
+    query: Result<String, String>,
+}
+
+impl CreateView {
+    /// Create statement for view of SELECT query
+    pub fn new<S: ToString>(name: S, query: &Sqlite3Builder) -> Self {
+        Self {
+            name: name.to_string(),
+            temporary: false,
+            if_not_exists: false,
+            columns: Vec::new(),
+            query: if query.is_select() {
+                statement_text(query, "view")
+            } else {
+                Err("Only SELECT is allowed in view".to_string())
+            },
+        }
+    }
+
+    /// Create TEMPORARY view
+    pub fn temporary(&mut self) -> &mut Self {
+        self.temporary = true;
+        self
+    }
+
+    /// Add IF NOT EXISTS
+    pub fn if_not_exists(&mut self) -> &mut Self {
+        self.if_not_exists = true;
+        self
+    }
+
+    /// Set names of view columns
+    pub fn columns<S: ToString>(&mut self, columns: &[S]) -> &mut Self {
+        self.columns = columns.iter().map(|col| col.to_string()).collect();
+        self
+    }
+
+    /// Build SQL command
+    pub fn sql(&self) -> Result<String, Error> {
+        let query = self
+            .query
+            .as_ref()
+            .map_err(|err| Error::Build(format!("View {}: {}", self.name, err)))?;
+        let temporary = if self.temporary { " TEMPORARY" } else { "" };
+        let if_not_exists = if self.if_not_exists {
+            " IF NOT EXISTS"
+        } else {
+            ""
+        };
+        let columns = if self.columns.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", self.columns.join(", "))
+        };
+        Ok(format!(
+            "CREATE{} VIEW{} {}{} AS {}",
+            temporary, if_not_exists, self.name, columns, query
+        ))
+    }
+
+    /// Execute statement
+    pub fn exec(&self, conn: &impl Executor) -> Result<(), Error> {
+        execute(conn, &self.sql()?)
+    }
+}
+
+/// CREATE TRIGGER statement.
+/// Body statements refer to changed row as `NEW` and `OLD`.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::Sqlite3Builder;
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let sql = Sqlite3Builder::create_trigger("trg_books_price", "books")
+///     .after()
+///     .on_update_of(&["price"])
+///     .for_each_row()
+///     .when("NEW.price > OLD.price")
+///     .statement(
+///         Sqlite3Builder::insert_into("price_log")
+///             .fields(&["book", "price"])
+///             .values(&["NEW.id", "NEW.price"]),
+///     )
+///     .sql()?;
+///
+/// assert_eq!("CREATE TRIGGER trg_books_price AFTER UPDATE OF price ON books FOR EACH ROW \
+///     WHEN NEW.price > OLD.price \
+///     BEGIN INSERT INTO price_log (book, price) VALUES (NEW.id, NEW.price); END;", &sql);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CreateTrigger {
+    name: String,
+    table: String,
+    temporary: bool,
+    if_not_exists: bool,
+    timing: Option<&'static str>,
+    event: Option<String>,
+    for_each_row: bool,
+    when: Vec<String>,
+    statements: Vec<Result<String, String>>,
+}
+
+impl CreateTrigger {
+    /// Create statement for trigger on table or view
+    pub fn new<S: ToString, T: ToString>(name: S, table: T) -> Self {
+        Self {
+            name: name.to_string(),
+            table: table.to_string(),
+            temporary: false,
+            if_not_exists: false,
+            timing: None,
+            event: None,
+            for_each_row: false,
+            when: Vec::new(),
+            statements: Vec::new(),
+        }
+    }
+
+    /// Create TEMPORARY trigger
+    pub fn temporary(&mut self) -> &mut Self {
+        self.temporary = true;
+        self
+    }
+
+    /// Add IF NOT EXISTS
+    pub fn if_not_exists(&mut self) -> &mut Self {
+        self.if_not_exists = true;
+        self
+    }
+
+    /// Fire BEFORE change, default
+    pub fn before(&mut self) -> &mut Self {
+        self.timing = Some("BEFORE");
+        self
+    }
+
+    /// Fire AFTER change
+    pub fn after(&mut self) -> &mut Self {
+        self.timing = Some("AFTER");
+        self
+    }
+
+    /// Fire INSTEAD OF change, for views only
+    pub fn instead_of(&mut self) -> &mut Self {
+        self.timing = Some("INSTEAD OF");
+        self
+    }
+
+    /// Fire on INSERT
+    pub fn on_insert(&mut self) -> &mut Self {
+        self.event = Some("INSERT".to_string());
+        self
+    }
+
+    /// Fire on UPDATE of any column
+    pub fn on_update(&mut self) -> &mut Self {
+        self.event = Some("UPDATE".to_string());
+        self
+    }
+
+    /// Fire on UPDATE of listed columns
+    pub fn on_update_of<S: ToString>(&mut self, columns: &[S]) -> &mut Self {
+        self.event = Some(format!("UPDATE OF {}", join(columns)));
+        self
+    }
+
+    /// Fire on DELETE
+    pub fn on_delete(&mut self) -> &mut Self {
+        self.event = Some("DELETE".to_string());
+        self
+    }
+
+    /// Add FOR EACH ROW
+    pub fn for_each_row(&mut self) -> &mut Self {
+        self.for_each_row = true;
+        self
+    }
+
+    /// Add WHEN condition
+    pub fn when<S: ToString>(&mut self, cond: S) -> &mut Self {
+        self.when.push(cond.to_string());
+        self
+    }
+
+    /// Add INSERT, UPDATE, DELETE or SELECT statement to body
+    pub fn statement(&mut self, query: &Sqlite3Builder) -> &mut Self {
+        self.statements.push(if query.has_with_or_returning() {
+            Err("WITH and RETURNING are not allowed in trigger".to_string())
+        } else {
+            statement_text(query, "trigger")
+        });
+        self
+    }
+
+    /// Build SQL command
+    pub fn sql(&self) -> Result<String, Error> {
+        let event = self
+            .event
+            .as_ref()
+            .ok_or_else(|| Error::Build(format!("No event of trigger {}", self.name)))?;
+        if self.statements.is_empty() {
+            return Err(Error::Build(format!(
+                "No statements in trigger {}",
+                self.name
+            )));
+        }
+        let statements = self
+            .statements
+            .iter()
+            .map(|stmt| {
+                stmt.clone()
+                    .map_err(|err| Error::Build(format!("Trigger {}: {}", self.name, err)))
+            })
+            .collect::<Result<Vec<String>, Error>>()?;
+
+        let temporary = if self.temporary { " TEMPORARY" } else { "" };
+        let if_not_exists = if self.if_not_exists {
+            " IF NOT EXISTS"
+        } else {
+            ""
+        };
+        let timing = match self.timing {
+            Some(timing) => format!("{} ", timing),
+            None => String::new(),
+        };
+        let for_each_row = if self.for_each_row {
+            " FOR EACH ROW"
+        } else {
+            ""
+        };
+        let when = match self.when.len() {
+            0 => String::new(),
+            1 => format!(" WHEN {}", self.when[0]),
+            _ => format!(" WHEN ({})", self.when.join(") AND (")),
+        };
+
+        Ok(format!(
+            "CREATE{} TRIGGER{} {} {}{} ON {}{}{} BEGIN {} END;",
+            temporary,
+            if_not_exists,
+            self.name,
+            timing,
+            event,
+            self.table,
+            for_each_row,
+            when,
+            statements.join(" ")
+        ))
+    }
+
+    /// Execute statement
+    pub fn exec(&self, conn: &impl Executor) -> Result<(), Error> {
+        execute(conn, &self.sql()?)
+    }
+}
+
+/// Join names with comma
+fn join<S: ToString>(names: &[S]) -> String {
+    names
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_table() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+
+        Sqlite3Builder::create_table("authors")
+            .column(Column::new("id", "INTEGER").primary_key())
+            .column(Column::new("name", "TEXT").not_null().unique())
+            .exec(&conn)?;
+
+        let mut books = Sqlite3Builder::create_table("books");
+        books
+            .column(Column::new("author", "INTEGER").not_null())
+            .column(Column::new("title", "TEXT").collate("NOCASE"))
+            .column(Column::new("price", "REAL").default(0))
+            .column(
+                Column::new("cents", "INTEGER").generated_stored("CAST(price * 100 AS INTEGER)"),
+            )
+            .primary_key(&["author", "title"])
+            .foreign_key(
+                &["author"],
+                ForeignKey::new(String::from("authors"), &[] as &[&str])
+                    .on_delete(FkAction::Cascade)
+                    .on_update(FkAction::Restrict)
+                    .deferred(),
+            )
+            .check("price < 1000")
+            .without_rowid()
+            .strict();
+
+        assert_eq!(
+            books.sql()?,
+            "CREATE TABLE books (author INTEGER NOT NULL, title TEXT COLLATE NOCASE, \
+             price REAL DEFAULT 0, \
+             cents INTEGER GENERATED ALWAYS AS (CAST(price * 100 AS INTEGER)) STORED, \
+             PRIMARY KEY (author, title), \
+             FOREIGN KEY (author) REFERENCES authors ON DELETE CASCADE ON UPDATE RESTRICT \
+             DEFERRABLE INITIALLY DEFERRED, \
+             CHECK (price < 1000)) WITHOUT ROWID, STRICT;"
+        );
+
+        books.exec(&conn)?;
+        assert!(books.exec(&conn).is_err());
+        books.if_not_exists().exec(&conn)?;
+
+        conn.execute("INSERT INTO authors VALUES (1, 'Herbert');")?;
+        conn.execute("INSERT INTO books (author, title, price) VALUES (1, 'Dune', 9.99);")?;
+        let err: Error = conn
+            .execute("INSERT INTO books (author, title, price) VALUES (1, 'Emma', 'free');")
+            .unwrap_err()
+            .into();
+        assert!(err.is_constraint());
+
+        let err = Sqlite3Builder::create_table("empty").sql().unwrap_err();
+        assert_eq!(err.to_string(), "No columns in table empty");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_index() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+        conn.execute(
+            "CREATE TABLE books (title TEXT, price INTEGER, isbn TEXT); \
+             INSERT INTO books VALUES ('Dune', 120, '1'), ('Emma', 0, '1');",
+        )?;
+
+        let mut priced = Sqlite3Builder::select_from("books");
+        priced
+            .and_where_gt("price", 0)
+            .and_where_is_not_null("isbn");
+        let mut db = Sqlite3Builder::create_index("idx_books_isbn", "books");
+        db.unique().if_not_exists().column("isbn").partial(&priced);
+
+        assert_eq!(
+            db.sql()?,
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_books_isbn ON books (isbn) \
+             WHERE (price > 0) AND (isbn IS NOT NULL);"
+        );
+
+        db.exec(&conn)?;
+        db.exec(&conn)?;
+
+        let err: Error = conn
+            .execute("INSERT INTO books VALUES ('Dune Messiah', 90, '1');")
+            .unwrap_err()
+            .into();
+        assert!(err.is_constraint());
+
+        let mut db = Sqlite3Builder::create_index("idx_books_title", "books");
+        db.column_collate("title", "NOCASE", false)
+            .column_order("price", true)
+            .column_order("length(title)", false);
+
+        assert_eq!(
+            db.sql()?,
+            "CREATE INDEX idx_books_title ON books \
+             (title COLLATE NOCASE, price DESC, length(title));"
+        );
+        db.exec(&conn)?;
+
+        Sqlite3Builder::drop_index("idx_books_title").exec(&conn)?;
+        assert!(Sqlite3Builder::drop_index("idx_books_title")
+            .exec(&conn)
+            .is_err());
+        Sqlite3Builder::drop_index("idx_books_title")
+            .if_exists()
+            .exec(&conn)?;
+
+        let err = Sqlite3Builder::create_index("idx_empty", "books")
+            .sql()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "No columns in index idx_empty");
+
+        let mut query = Sqlite3Builder::select_from("books");
+        let price = query.param(0);
+        query.and_where_gt("price", price);
+        let err = Sqlite3Builder::create_index("idx_books_price", "books")
+            .column("price")
+            .partial(&query)
+            .sql()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parameters are not allowed in WHERE of schema object"
+        );
+
+        let mut query = Sqlite3Builder::select_from("books");
+        query
+            .join("authors")
+            .on("1")
+            .group_by("title")
+            .distinct()
+            .and_where_gt("price", 0);
+        let err = Sqlite3Builder::create_index("idx_books_price", "books")
+            .column("price")
+            .partial(&query)
+            .sql()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Only WHERE conditions of SELECT from books are allowed"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_view_and_trigger() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+        conn.execute(
+            "CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT, price INTEGER); \
+             CREATE TABLE price_log (book INTEGER, old INTEGER, new INTEGER); \
+             INSERT INTO books VALUES (1, 'Dune', 120), (2, 'Emma', 80);",
+        )?;
+
+        let mut view = Sqlite3Builder::create_view(
+            "cheap",
+            Sqlite3Builder::select_from("books")
+                .fields(&["id", "title"])
+                .and_where_lt("price", 100),
+        );
+        view.temporary().columns(&["book", "name"]);
+        assert_eq!(
+            view.sql()?,
+            "CREATE TEMPORARY VIEW cheap (book, name) AS \
+             SELECT id, title FROM books WHERE price < 100;"
+        );
+        view.exec(&conn)?;
+
+        let mut log = Sqlite3Builder::create_trigger("trg_log", "books");
+        log.after()
+            .on_update_of(&["price"])
+            .for_each_row()
+            .when("NEW.price <> OLD.price")
+            .when("NEW.price > 0")
+            .statement(
+                Sqlite3Builder::insert_into("price_log")
+                    .fields(&["book", "old", "new"])
+                    .values(&["NEW.id", "OLD.price", "NEW.price"]),
+            );
+        log.exec(&conn)?;
+
+        let mut rename = Sqlite3Builder::create_trigger("trg_rename", "cheap");
+        rename
+            .instead_of()
+            .on_update()
+            .statement(
+                Sqlite3Builder::update_table("books")
+                    .set("title", "NEW.name")
+                    .and_where_eq("id", "OLD.book"),
+            )
+            .statement(Sqlite3Builder::delete_from("price_log").and_where_eq("book", "OLD.book"));
+        assert_eq!(
+            rename.sql()?,
+            "CREATE TRIGGER trg_rename INSTEAD OF UPDATE ON cheap BEGIN \
+             UPDATE books SET title = NEW.name WHERE id = OLD.book; \
+             DELETE FROM price_log WHERE book = OLD.book; END;"
+        );
+        rename.exec(&conn)?;
+
+        conn.execute("UPDATE books SET price = 90 WHERE id = 1;")?;
+        conn.execute("UPDATE cheap SET name = 'Dune Messiah' WHERE book = 1;")?;
+        let title = Sqlite3Builder::select_from("books")
+            .field("title")
+            .and_where_eq("id", 1)
+            .get_string(&conn)?;
+        assert_eq!(title, "Dune Messiah");
+        let logged = Sqlite3Builder::select_from("price_log")
+            .field("COUNT(*)")
+            .get_i64(&conn)?;
+        assert_eq!(logged, 0);
+        conn.execute("UPDATE books SET price = 70 WHERE id = 2;")?;
+        let logged = Sqlite3Builder::select_from("price_log")
+            .field("old")
+            .get_i64(&conn)?;
+        assert_eq!(logged, 80);
+
+        let mut db = Sqlite3Builder::delete_from("books");
+        let id = db.param(1);
+        db.and_where_eq("id", &id);
+        let err = Sqlite3Builder::create_trigger("trg_bad", "books")
+            .on_delete()
+            .statement(&db)
+            .sql()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Trigger trg_bad: Parameters are not allowed in trigger"
+        );
+        let err = Sqlite3Builder::create_trigger("trg_bad", "books")
+            .statement(&db)
+            .sql()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "No event of trigger trg_bad");
+        let mut db = Sqlite3Builder::delete_from("books");
+        db.and_where("id = OLD.id").returning(&["id"]);
+        let err = Sqlite3Builder::create_trigger("trg_bad", "books")
+            .on_delete()
+            .statement(&db)
+            .sql()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Trigger trg_bad: WITH and RETURNING are not allowed in trigger"
+        );
+        let mut cte = Sqlite3Builder::select_from("old");
+        cte.field("id");
+        let mut db = Sqlite3Builder::delete_from("books");
+        db.with("old", &cte).and_where("id IN old");
+        let err = Sqlite3Builder::create_trigger("trg_bad", "books")
+            .on_delete()
+            .statement(&db)
+            .sql()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Trigger trg_bad: WITH and RETURNING are not allowed in trigger"
+        );
+        let err = Sqlite3Builder::create_view("bad", &Sqlite3Builder::delete_from("books"))
+            .sql()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "View bad: Only SELECT is allowed in view");
+
+        Sqlite3Builder::drop_trigger("trg_rename").exec(&conn)?;
+        Sqlite3Builder::drop_trigger("trg_rename")
+            .if_exists()
+            .exec(&conn)?;
+        Sqlite3Builder::drop_view("cheap").exec(&conn)?;
+        assert!(Sqlite3Builder::drop_view("cheap").exec(&conn).is_err());
+        assert_eq!(
+            Sqlite3Builder::drop_table("price_log").if_exists().sql()?,
+            "DROP TABLE IF EXISTS price_log;"
+        );
+
+        Ok(())
+    }
+}
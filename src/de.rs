@@ -0,0 +1,130 @@
+//! Deserialization of result rows into serde types.
+
+use crate::error::Error;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::value::Value as JValue;
+use std::cell::RefCell;
+use std::iter::{Enumerate, Zip};
+use std::slice::Iter;
+use std::vec::IntoIter;
+
+/// Deserialize row keyed by column names
+pub fn from_row<T: DeserializeOwned>(columns: &[String], values: Vec<JValue>) -> Result<T, Error> {
+    let failed = RefCell::new(None);
+    let row = RowDeserializer {
+        fields: columns.iter().zip(values).enumerate(),
+        failed: &failed,
+    };
+    T::deserialize(row).map_err(|err| match failed.into_inner() {
+        Some(err) => err,
+        None => Error::conversion(err),
+    })
+}
+
+/// Deserializer presenting row as map of columns
+struct RowDeserializer<'a> {
+    fields: Enumerate<Zip<Iter<'a, String>, IntoIter<JValue>>>,
+    failed: &'a RefCell<Option<Error>>,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for RowDeserializer<'a> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(RowAccess {
+            fields: self.fields,
+            current: None,
+            failed: self.failed,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Map access over row columns.
+/// Remembers failed column, as serde errors carry only text.
+struct RowAccess<'a> {
+    fields: Enumerate<Zip<Iter<'a, String>, IntoIter<JValue>>>,
+    current: Option<(usize, &'a String, JValue)>,
+    failed: &'a RefCell<Option<Error>>,
+}
+
+impl<'de, 'a> MapAccess<'de> for RowAccess<'a> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.fields.next() {
+            Some((idx, (name, value))) => {
+                let key = seed.deserialize(name.as_str().into_deserializer())?;
+                self.current = Some((idx, name, value));
+                Ok(Some(key))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (idx, name, value) = self
+            .current
+            .take()
+            .ok_or_else(|| de::Error::custom("Column value requested before its name"))?;
+        seed.deserialize(value).map_err(|err| {
+            let msg = err.to_string();
+            *self.failed.borrow_mut() = Some(Error::conversion(&msg).at_column(idx, name));
+            de::Error::custom(msg)
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Book {
+        title: String,
+        price: i64,
+        comment: Option<String>,
+    }
+
+    #[test]
+    fn test_from_row() -> Result<(), Error> {
+        let columns = vec!["price".to_string(), "title".to_string()];
+
+        let book: Book = from_row(&columns, vec![120.into(), "Dune".into()])?;
+        assert_eq!(
+            book,
+            Book {
+                title: "Dune".to_string(),
+                price: 120,
+                comment: None,
+            }
+        );
+
+        let err = from_row::<Book>(&columns, vec!["cheap".into(), "Dune".into()]).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Column 0 (price): invalid type"));
+
+        let err = from_row::<Book>(&columns[1..], vec!["Dune".into()]).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `price`");
+
+        Ok(())
+    }
+}
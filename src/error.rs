@@ -0,0 +1,269 @@
+//! Error type of the crate.
+
+use std::fmt;
+
+/// Primary SQLite result code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// Generic error (SQLITE_ERROR)
+    Error,
+    /// Internal logic error (SQLITE_INTERNAL)
+    Internal,
+    /// Access permission denied (SQLITE_PERM)
+    Perm,
+    /// Callback routine requested an abort (SQLITE_ABORT)
+    Abort,
+    /// Database file is locked (SQLITE_BUSY)
+    Busy,
+    /// Table in the database is locked (SQLITE_LOCKED)
+    Locked,
+    /// Memory allocation failed (SQLITE_NOMEM)
+    NoMem,
+    /// Attempt to write a readonly database (SQLITE_READONLY)
+    ReadOnly,
+    /// Operation interrupted (SQLITE_INTERRUPT)
+    Interrupt,
+    /// Disk I/O error (SQLITE_IOERR)
+    IoErr,
+    /// Database disk image is malformed (SQLITE_CORRUPT)
+    Corrupt,
+    /// Unknown opcode or VFS (SQLITE_NOTFOUND)
+    NotFound,
+    /// Database is full (SQLITE_FULL)
+    Full,
+    /// Unable to open the database file (SQLITE_CANTOPEN)
+    CantOpen,
+    /// Database lock protocol error (SQLITE_PROTOCOL)
+    Protocol,
+    /// Database schema changed (SQLITE_SCHEMA)
+    Schema,
+    /// String or BLOB exceeds size limit (SQLITE_TOOBIG)
+    TooBig,
+    /// Constraint violation (SQLITE_CONSTRAINT)
+    Constraint,
+    /// Data type mismatch (SQLITE_MISMATCH)
+    Mismatch,
+    /// Library used incorrectly (SQLITE_MISUSE)
+    Misuse,
+    /// Large file support is disabled (SQLITE_NOLFS)
+    NoLfs,
+    /// Authorization denied (SQLITE_AUTH)
+    Auth,
+    /// Bind parameter index out of range (SQLITE_RANGE)
+    Range,
+    /// File is not a database (SQLITE_NOTADB)
+    NotADb,
+    /// Any other code
+    Other(isize),
+}
+
+impl ErrorCode {
+    /// Map primary or extended result code
+    pub fn from_code(code: isize) -> Self {
+        match code & 0xff {
+            1 => ErrorCode::Error,
+            2 => ErrorCode::Internal,
+            3 => ErrorCode::Perm,
+            4 => ErrorCode::Abort,
+            5 => ErrorCode::Busy,
+            6 => ErrorCode::Locked,
+            7 => ErrorCode::NoMem,
+            8 => ErrorCode::ReadOnly,
+            9 => ErrorCode::Interrupt,
+            10 => ErrorCode::IoErr,
+            11 => ErrorCode::Corrupt,
+            12 => ErrorCode::NotFound,
+            13 => ErrorCode::Full,
+            14 => ErrorCode::CantOpen,
+            15 => ErrorCode::Protocol,
+            17 => ErrorCode::Schema,
+            18 => ErrorCode::TooBig,
+            19 => ErrorCode::Constraint,
+            20 => ErrorCode::Mismatch,
+            21 => ErrorCode::Misuse,
+            22 => ErrorCode::NoLfs,
+            23 => ErrorCode::Auth,
+            25 => ErrorCode::Range,
+            26 => ErrorCode::NotADb,
+            _ => ErrorCode::Other(code),
+        }
+    }
+}
+
+/// Error of building or executing SQL
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// use sqlite3builder::{Error, ErrorCode, Sqlite3Builder};
+///
+/// let conn = sqlite3::open(":memory:").unwrap();
+/// conn.execute("CREATE TABLE books (title TEXT UNIQUE);").unwrap();
+///
+/// let mut db = Sqlite3Builder::insert_into("books");
+/// db.field("title").values(&["'Dune'"]);
+///
+/// db.exec(&conn).unwrap();
+///
+/// match db.exec(&conn) {
+///     Err(Error::Sqlite { code: Some(ErrorCode::Constraint), .. }) => println!("Duplicate title"),
+///     Err(err) if err.is_busy() => println!("Retry later"),
+///     res => {
+///         res.unwrap();
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub enum Error {
+    /// SQL command cannot be built
+    Build(String),
+    /// SQLite failed with result code
+    Sqlite {
+        /// Result code, if SQLite reported one
+        code: Option<ErrorCode>,
+        /// Error message
+        message: String,
+    },
+    /// Connection pool failed
+    Pool(r2d2::Error),
+    /// Query returned no rows
+    NoRows,
+    /// Value cannot be converted
+    Conversion {
+        /// Column index
+        index: Option<usize>,
+        /// Column name
+        name: Option<String>,
+        /// Reason
+        message: String,
+    },
+    /// Migration cannot be loaded or applied
+    Migration(String),
+}
+
+impl Error {
+    /// Conversion error not bound to a column yet
+    pub fn conversion<S: ToString>(message: S) -> Self {
+        Error::Conversion {
+            index: None,
+            name: None,
+            message: message.to_string(),
+        }
+    }
+
+    /// Bind conversion error to column
+    pub fn at_column(self, idx: usize, column: &str) -> Self {
+        match self {
+            Error::Conversion {
+                index: None,
+                name: None,
+                message,
+            } => Error::Conversion {
+                index: Some(idx),
+                name: Some(column.to_string()),
+                message,
+            },
+            err => err,
+        }
+    }
+
+    /// SQLite result code
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Error::Sqlite { code, .. } => *code,
+            _ => None,
+        }
+    }
+
+    /// True if database is busy or locked
+    pub fn is_busy(&self) -> bool {
+        matches!(self.code(), Some(ErrorCode::Busy) | Some(ErrorCode::Locked))
+    }
+
+    /// True if constraint is violated
+    pub fn is_constraint(&self) -> bool {
+        self.code() == Some(ErrorCode::Constraint)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Build(message) => write!(f, "{}", message),
+            Error::Sqlite { message, .. } => write!(f, "{}", message),
+            Error::Pool(err) => write!(f, "Pool error: {}", err),
+            Error::NoRows => write!(f, "No any row"),
+            Error::Conversion {
+                index,
+                name,
+                message,
+            } => match (index, name) {
+                (Some(index), Some(name)) => write!(f, "Column {} ({}): {}", index, name, message),
+                (None, Some(name)) => write!(f, "Column {}: {}", name, message),
+                (Some(index), None) => write!(f, "Column {}: {}", index, message),
+                (None, None) => write!(f, "{}", message),
+            },
+            Error::Migration(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Pool(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlite3::Error> for Error {
+    fn from(err: sqlite3::Error) -> Self {
+        let message = err.to_string();
+        Error::Sqlite {
+            code: err.code.map(ErrorCode::from_code),
+            message,
+        }
+    }
+}
+
+impl From<r2d2::Error> for Error {
+    fn from(err: r2d2::Error) -> Self {
+        Error::Pool(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_error_code() {
+        let conn = sqlite3::open(":memory:").unwrap();
+        conn.execute("CREATE TABLE books (title TEXT NOT NULL);")
+            .unwrap();
+
+        let err: Error = conn
+            .execute("INSERT INTO books VALUES (NULL);")
+            .unwrap_err()
+            .into();
+
+        assert!(err.is_constraint());
+        assert!(!err.is_busy());
+        assert_eq!(err.code(), Some(ErrorCode::Constraint));
+        assert_eq!(ErrorCode::from_code(2067), ErrorCode::Constraint);
+        assert_eq!(ErrorCode::from_code(1000), ErrorCode::Other(1000));
+    }
+
+    #[test]
+    fn test_conversion_display() {
+        let err = Error::conversion("Expected Integer, found Null");
+        assert_eq!(err.to_string(), "Expected Integer, found Null");
+
+        let err = err.at_column(1, "price");
+        assert_eq!(
+            err.to_string(),
+            "Column 1 (price): Expected Integer, found Null"
+        );
+    }
+}
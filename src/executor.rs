@@ -0,0 +1,101 @@
+//! Connections which may execute builder statements.
+
+use crate::error::Error;
+use crate::transaction::{Savepoint, Transaction};
+use crate::ConnPooled;
+use sqlite3::{Connection, Statement};
+
+/// Connection which may execute builder statements.
+/// Implemented for bare and pooled Sqlite3 connections, transactions and savepoints.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::Sqlite3Builder;
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let conn = sqlite3::open(":memory:")?;
+///
+/// let answer = Sqlite3Builder::select_values(&["42"]).get_int(&conn)?;
+///
+/// assert_eq!(42, answer);
+/// # Ok(())
+/// # }
+/// ```
+pub trait Executor {
+    /// Underlying Sqlite3 connection
+    fn connection(&self) -> &Connection;
+
+    /// Prepare statement and pass it to closure.
+    /// Executors with statement cache reuse statements prepared before.
+    fn with_statement<T, F>(&self, sql: &str, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Statement) -> Result<T, Error>,
+    {
+        let mut stmt = self.connection().prepare(sql)?;
+        f(&mut stmt)
+    }
+
+    /// Read counters of the last executed statement.
+    /// Executors with statement cache keep the counters statement prepared.
+    fn exec_result(&self) -> Result<ExecResult, Error> {
+        let mut stmt = self.connection().prepare(ExecResult::SQL)?;
+        ExecResult::read(&mut stmt)
+    }
+}
+
+impl Executor for Connection {
+    fn connection(&self) -> &Connection {
+        self
+    }
+}
+
+impl Executor for ConnPooled {
+    fn connection(&self) -> &Connection {
+        self
+    }
+}
+
+impl<'a> Executor for Transaction<'a> {
+    fn connection(&self) -> &Connection {
+        self
+    }
+}
+
+impl<'a> Executor for Savepoint<'a> {
+    fn connection(&self) -> &Connection {
+        self
+    }
+}
+
+/// Outcome of executed statement
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExecResult {
+    changes: u64,
+    last_insert_rowid: i64,
+}
+
+impl ExecResult {
+    /// Query of counters of last statement executed on connection
+    pub(crate) const SQL: &'static str = "SELECT changes(), last_insert_rowid();";
+
+    /// Read counters by statement prepared from `SQL`
+    pub(crate) fn read(stmt: &mut Statement) -> Result<Self, Error> {
+        stmt.next()?;
+        Ok(Self {
+            changes: stmt.read::<i64>(0)? as u64,
+            last_insert_rowid: stmt.read::<i64>(1)?,
+        })
+    }
+
+    /// Number of rows inserted, updated or deleted by INSERT, UPDATE or DELETE
+    pub fn changes(&self) -> u64 {
+        self.changes
+    }
+
+    /// Rowid of the most recent successful INSERT on the connection
+    pub fn last_insert_rowid(&self) -> i64 {
+        self.last_insert_rowid
+    }
+}
@@ -0,0 +1,195 @@
+//! Conversion of Sqlite3 values to JSON.
+
+use crate::error::Error;
+use serde_json::value::Value as JValue;
+use serde_json::Number;
+use sqlite3::Value as SValue;
+
+/// Representation of BLOB values in JSON results
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlobEncoding {
+    /// Standard base64 string with padding
+    #[default]
+    Base64,
+    /// Lowercase hexadecimal string
+    Hex,
+    /// Array of byte numbers
+    Array,
+}
+
+/// Representation of NaN and infinite REAL values in JSON results.
+/// JSON has no notation for them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Convert to `null`
+    #[default]
+    Null,
+    /// Convert to string: `"NaN"`, `"inf"` or `"-inf"`
+    String,
+    /// Fail conversion
+    Error,
+}
+
+/// Options for converting Sqlite3 values to JSON
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// BLOB representation
+    pub blob: BlobEncoding,
+    /// NaN and infinity representation
+    pub non_finite: NonFinite,
+}
+
+impl JsonOptions {
+    /// Convert sqlite3::Value to serde_json::Value
+    pub fn convert(&self, src: &SValue) -> Result<JValue, Error> {
+        match src {
+            SValue::Null => Ok(JValue::Null),
+            SValue::Integer(val) => Ok(JValue::Number((*val).into())),
+            SValue::Float(val) => match Number::from_f64(*val) {
+                Some(num) => Ok(JValue::Number(num)),
+                None => match self.non_finite {
+                    NonFinite::Null => Ok(JValue::Null),
+                    NonFinite::String => Ok(JValue::String(val.to_string())),
+                    NonFinite::Error => Err(Error::conversion(format!("Non-finite float {}", val))),
+                },
+            },
+            SValue::String(val) => Ok(JValue::String(val.clone())),
+            SValue::Binary(val) => Ok(match self.blob {
+                BlobEncoding::Base64 => JValue::String(base64(val)),
+                BlobEncoding::Hex => JValue::String(hex(val)),
+                BlobEncoding::Array => {
+                    JValue::Array(val.iter().map(|byte| JValue::from(*byte)).collect())
+                }
+            }),
+        }
+    }
+}
+
+/// Make unique object keys from result column names.
+/// Repeated names get suffix with occurrence number: `id`, `id_2`, `id_3`.
+/// Suffixes skip names already present in the result.
+pub fn object_keys(columns: &[String]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::with_capacity(columns.len());
+    for name in columns {
+        let mut key = name.clone();
+        let mut num = 1;
+        while keys.contains(&key) || (num > 1 && columns.contains(&key)) {
+            num += 1;
+            key = format!("{}_{}", name, num);
+        }
+        keys.push(key);
+    }
+    keys
+}
+
+/// Encode bytes to base64 with padding
+fn base64(src: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(src.len().div_ceil(3) * 4);
+    for chunk in src.chunks(3) {
+        let b0 = chunk[0] as usize;
+        let b1 = chunk.get(1).map_or(0, |b| *b as usize);
+        let b2 = chunk.get(2).map_or(0, |b| *b as usize);
+        text.push(ALPHABET[b0 >> 2] as char);
+        text.push(ALPHABET[((b0 & 0x03) << 4) | (b1 >> 4)] as char);
+        if chunk.len() > 1 {
+            text.push(ALPHABET[((b1 & 0x0f) << 2) | (b2 >> 6)] as char);
+        } else {
+            text.push('=');
+        }
+        if chunk.len() > 2 {
+            text.push(ALPHABET[b2 & 0x3f] as char);
+        } else {
+            text.push('=');
+        }
+    }
+    text
+}
+
+/// Encode bytes to lowercase hex
+fn hex(src: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut text = String::with_capacity(src.len() * 2);
+    for byte in src {
+        text.push(DIGITS[(byte >> 4) as usize] as char);
+        text.push(DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xff, 0xfe]), "//4=");
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[]), "");
+        assert_eq!(hex(&[0x00, 0x0f, 0xa5, 0xff]), "000fa5ff");
+    }
+
+    #[test]
+    fn test_object_keys() {
+        let columns = ["id", "title", "id", "id_2", "id"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            object_keys(&columns),
+            vec!["id", "title", "id_3", "id_2", "id_4"]
+        );
+    }
+
+    #[test]
+    fn test_convert_float() -> Result<(), Error> {
+        let opts = JsonOptions::default();
+        assert_eq!(opts.convert(&SValue::Float(1.5))?, serde_json::json!(1.5));
+        assert_eq!(opts.convert(&SValue::Float(f64::INFINITY))?, JValue::Null);
+
+        let opts = JsonOptions {
+            non_finite: NonFinite::String,
+            ..JsonOptions::default()
+        };
+        assert_eq!(opts.convert(&SValue::Float(f64::NEG_INFINITY))?, "-inf");
+
+        let opts = JsonOptions {
+            non_finite: NonFinite::Error,
+            ..JsonOptions::default()
+        };
+        assert!(opts.convert(&SValue::Float(f64::NAN)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_blob() -> Result<(), Error> {
+        let blob = SValue::Binary(vec![1, 2, 255]);
+
+        let opts = JsonOptions::default();
+        assert_eq!(opts.convert(&blob)?, "AQL/");
+
+        let opts = JsonOptions {
+            blob: BlobEncoding::Hex,
+            ..JsonOptions::default()
+        };
+        assert_eq!(opts.convert(&blob)?, "0102ff");
+
+        let opts = JsonOptions {
+            blob: BlobEncoding::Array,
+            ..JsonOptions::default()
+        };
+        assert_eq!(opts.convert(&blob)?, serde_json::json!([1, 2, 255]));
+
+        Ok(())
+    }
+}
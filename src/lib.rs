@@ -45,6 +45,10 @@
 //! - INSERT
 //! - UPDATE
 //! - DELETE
+//! - CREATE TABLE
+//! - CREATE INDEX, DROP INDEX
+//! - ALTER TABLE, table rebuild
+//! - CREATE VIEW, CREATE TRIGGER, DROP TABLE, DROP VIEW, DROP TRIGGER
 //!
 //! ### Operations
 //!
@@ -54,10 +58,23 @@
 //! - order by
 //! - where
 //! - limit, offset
+//! - window functions
+//! - returning
+//! - upsert (on conflict do nothing / do update)
+//! - conflict resolution (or ignore, or replace, ...)
 //! - subquery
+//! - common table expressions (with, with recursive)
+//! - bind parameters
+//! - affected rows and last insert rowid
+//! - transactions, savepoints
+//! - prepared statement cache
+//! - versioned schema migrations, down migrations, migration status
+//! - schema introspection, schema diff as SQL script
 //! - get all results
 //! - get first row
+//! - get rows as JSON objects or typed values
 //! - get first value, first integer value, first string value
+//! - get typed scalar values with optional type coercion
 //!
 //! ### Functions
 //!
@@ -72,21 +89,99 @@
 extern crate log;
 extern crate sql_builder;
 
+use cte::{Cte, Hint};
+use serde::de::DeserializeOwned;
 use serde_json::value::Value as JValue;
+use serde_json::Map as JMap;
 use sql_builder::{esc as SqlBuilderEsc, quote as SqlBuilderQuote, SqlBuilder};
-use sqlite3::Cursor;
 use sqlite3::Value as SValue;
-use std::error::Error;
+use sqlite3::{Cursor, State, Statement};
+use tail::QueryTail;
+use upsert::OnConflict;
+
+mod alter;
+mod cache;
+mod cte;
+mod ddl;
+mod de;
+mod error;
+mod executor;
+mod json;
+mod migrate;
+mod row;
+pub mod schema;
+mod tail;
+mod transaction;
+mod upsert;
+mod value;
+mod window;
+
+pub use alter::{AlterTable, TableRebuild};
+pub use cache::{CachedConnection, DEFAULT_CACHE_CAPACITY};
+pub use ddl::{
+    Column, CreateIndex, CreateTable, CreateTrigger, CreateView, DropStatement, FkAction,
+    ForeignKey, ToSql,
+};
+pub use error::{Error, ErrorCode};
+pub use executor::{ExecResult, Executor};
+pub use json::{BlobEncoding, JsonOptions, NonFinite};
+pub use migrate::{Migration, MigrationState, MigrationStatus, Migrator, DEFAULT_MIGRATIONS_TABLE};
+pub use row::{FromRow, Row};
+pub use transaction::{transaction, transaction_with, Behavior, Savepoint, Transaction};
+pub use value::{FromValue, ToValue};
+pub use window::{
+    aggregate, dense_rank, lag, lead, ntile, rank, row_number, FrameBound, Window, WindowFunction,
+};
 
 /// Pooled Sqlite3 connection
 type ConnPooled = r2d2::PooledConnection<r2d2_sqlite3::SqliteConnectionManager>;
 
+/// Kind of built statement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
 /// Main Sqlite3 builder
 pub struct Sqlite3Builder {
     builder: SqlBuilder,
+    kind: Kind,
+    ctes: Vec<Cte>,
+    recursive: bool,
+    fields: Vec<String>,
+    windows: Vec<String>,
+    tail: QueryTail,
+    json: JsonOptions,
+    params: Vec<SValue>,
+    coerce: bool,
+    conflict: Option<&'static str>,
+    upserts: Vec<OnConflict>,
+    returning: Vec<String>,
 }
 
 impl Sqlite3Builder {
+    /// Wrap SQL builder with default settings
+    fn from_builder(builder: SqlBuilder, kind: Kind) -> Self {
+        Self {
+            builder,
+            kind,
+            ctes: Vec::new(),
+            recursive: false,
+            fields: Vec::new(),
+            windows: Vec::new(),
+            tail: QueryTail::default(),
+            json: JsonOptions::default(),
+            params: Vec::new(),
+            coerce: false,
+            conflict: None,
+            upserts: Vec::new(),
+            returning: Vec::new(),
+        }
+    }
+
     /// Create SELECT query.
     /// You may specify comma separted list of tables.
     ///
@@ -111,9 +206,7 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn select_from<S: ToString>(table: S) -> Self {
-        Self {
-            builder: SqlBuilder::select_from(table),
-        }
+        Self::from_builder(SqlBuilder::select_from(table), Kind::Select)
     }
 
     /// Create SELECT query without a table.
@@ -135,9 +228,7 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn select_values<S: ToString>(values: &[S]) -> Self {
-        Self {
-            builder: SqlBuilder::select_values(values),
-        }
+        Self::from_builder(SqlBuilder::select_values(values), Kind::Select)
     }
 
     /// Create INSERT query.
@@ -163,9 +254,35 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn insert_into<S: ToString>(table: S) -> Self {
-        Self {
-            builder: SqlBuilder::insert_into(table),
-        }
+        Self::from_builder(SqlBuilder::insert_into(table), Kind::Insert)
+    }
+
+    /// Create INSERT OR REPLACE query.
+    /// Same as `insert_into` with `or_replace`.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::replace_into("books")
+    ///     .field("isbn")
+    ///     .field("title")
+    ///     .values(&["'9780441013593', 'Dune'"])
+    ///     .sql()?;
+    ///
+    /// assert_eq!("INSERT OR REPLACE INTO books (isbn, title) VALUES ('9780441013593', 'Dune');", &sql);
+    /// // add                             ^^^^^
+    /// // here                            table
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn replace_into<S: ToString>(table: S) -> Self {
+        let mut builder = Self::insert_into(table);
+        builder.or_replace();
+        builder
     }
 
     /// Create UPDATE query.
@@ -188,9 +305,7 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn update_table<S: ToString>(table: S) -> Self {
-        Self {
-            builder: SqlBuilder::update_table(table),
-        }
+        Self::from_builder(SqlBuilder::update_table(table), Kind::Update)
     }
 
     /// Create DELETE query.
@@ -213,9 +328,250 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn delete_from<S: ToString>(table: S) -> Self {
-        Self {
-            builder: SqlBuilder::delete_from(table),
+        Self::from_builder(SqlBuilder::delete_from(table), Kind::Delete)
+    }
+
+    /// Add common table expression to WITH clause.
+    /// Name may contain column list: `name(col1, col2)`.
+    /// Bind parameters of subquery are taken over if builder has none yet,
+    /// otherwise create them on the main builder with `param`.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut cheap = Sqlite3Builder::select_from("books");
+    /// cheap.field("title").and_where("price < 100");
+    ///
+    /// let sql = Sqlite3Builder::select_from("cheap")
+    ///     .with("cheap", &cheap)
+    ///     .field("title")
+    ///     .sql()?;
+    ///
+    /// assert_eq!("WITH cheap AS (SELECT title FROM books WHERE price < 100) SELECT title FROM cheap;", &sql);
+    /// // add           ^^^^^     ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+    /// // here          name                        query
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with<S: ToString>(&mut self, name: S, query: &Sqlite3Builder) -> &mut Self {
+        self.add_cte(name.to_string(), Hint::Auto, query)
+    }
+
+    /// Add common table expression and make WITH clause recursive.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+    /// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+    /// # let conn = pool.get()?;
+    /// # conn.execute("CREATE TABLE categories (id INTEGER PRIMARY KEY, parent INTEGER, name TEXT); \
+    /// #     INSERT INTO categories VALUES (1, NULL, 'Books'), (2, 1, 'Fiction'), (3, 2, 'Fantasy'), (4, NULL, 'Music');")?;
+    /// let mut tree = Sqlite3Builder::select_from("categories");
+    /// tree.fields(&["id", "name"])
+    ///     .and_where("id = 1")
+    ///     .union_all(
+    ///         Sqlite3Builder::select_from("categories AS c")
+    ///             .fields(&["c.id", "c.name"])
+    ///             .join("tree AS t")
+    ///             .on("c.parent = t.id")
+    ///             .query()?,
+    ///     );
+    ///
+    /// let names = Sqlite3Builder::select_from("tree")
+    ///     .with_recursive("tree(id, name)", &tree)
+    ///     .field("name")
+    ///     .order_asc("id")
+    ///     .get_as::<(String,)>(&conn)?;
+    ///
+    /// assert_eq!(names, vec![
+    ///     ("Books".to_string(),),
+    ///     ("Fiction".to_string(),),
+    ///     ("Fantasy".to_string(),),
+    /// ]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_recursive<S: ToString>(&mut self, name: S, query: &Sqlite3Builder) -> &mut Self {
+        self.recursive = true;
+        self.add_cte(name.to_string(), Hint::Auto, query)
+    }
+
+    /// Add common table expression with MATERIALIZED hint.
+    /// Requires SQLite 3.35.0 or later.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut stats = Sqlite3Builder::select_from("books");
+    /// stats.field("AVG(price) AS avg_price");
+    ///
+    /// let sql = Sqlite3Builder::delete_from("books")
+    ///     .with_materialized("stats", &stats)
+    ///     .and_where("price > (SELECT avg_price * 10 FROM stats)")
+    ///     .sql()?;
+    ///
+    /// assert_eq!("WITH stats AS MATERIALIZED (SELECT AVG(price) AS avg_price FROM books) \
+    ///     DELETE FROM books WHERE price > (SELECT avg_price * 10 FROM stats);", &sql);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_materialized<S: ToString>(&mut self, name: S, query: &Sqlite3Builder) -> &mut Self {
+        self.add_cte(name.to_string(), Hint::Materialized, query)
+    }
+
+    /// Add common table expression with NOT MATERIALIZED hint.
+    /// Requires SQLite 3.35.0 or later.
+    pub fn with_not_materialized<S: ToString>(
+        &mut self,
+        name: S,
+        query: &Sqlite3Builder,
+    ) -> &mut Self {
+        self.add_cte(name.to_string(), Hint::NotMaterialized, query)
+    }
+
+    /// Add common table expression and take over its parameters
+    fn add_cte(&mut self, name: String, hint: Hint, query: &Sqlite3Builder) -> &mut Self {
+        let mut text = query
+            .sql()
+            .map(|sql| sql.trim_end_matches(';').to_string())
+            .map_err(|err| err.to_string());
+        if !query.params.is_empty() {
+            if self.params.is_empty() {
+                self.params = query.params.clone();
+            } else {
+                text = Err("Subquery parameters clash with builder parameters".to_string());
+            }
         }
+        self.ctes.push(Cte::new(name, hint, text));
+        self
+    }
+
+    /// Create CREATE TABLE statement.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::{Column, Sqlite3Builder};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::create_table("books")
+    ///     .column(Column::new("id", "INTEGER").primary_key())
+    ///     .column(Column::new("title", "TEXT").not_null())
+    ///     .strict()
+    ///     .sql()?;
+    ///
+    /// assert_eq!("CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT NOT NULL) STRICT;", &sql);
+    /// // add                   ^^^^^
+    /// // here                  table
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_table<S: ToString>(table: S) -> CreateTable {
+        CreateTable::new(table)
+    }
+
+    /// Create CREATE INDEX statement.
+    /// WHERE of partial index is taken from SELECT built with `and_where` helpers.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut priced = Sqlite3Builder::select_from("books");
+    /// priced.and_where_is_not_null("price");
+    ///
+    /// let sql = Sqlite3Builder::create_index("idx_books_price", "books")
+    ///     .column("author")
+    ///     .column_order("price", true)
+    ///     .partial(&priced)
+    ///     .sql()?;
+    ///
+    /// assert_eq!("CREATE INDEX idx_books_price ON books (author, price DESC) WHERE price IS NOT NULL;", &sql);
+    /// // add                   ^^^^^^^^^^^^^^^    ^^^^^
+    /// // here                       index         table
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_index<S: ToString, T: ToString>(name: S, table: T) -> CreateIndex {
+        CreateIndex::new(name, table)
+    }
+
+    /// Create DROP INDEX statement.
+    pub fn drop_index<S: ToString>(name: S) -> DropStatement {
+        DropStatement::new("INDEX", name.to_string())
+    }
+
+    /// Create DROP TABLE statement.
+    pub fn drop_table<S: ToString>(name: S) -> DropStatement {
+        DropStatement::new("TABLE", name.to_string())
+    }
+
+    /// Create CREATE VIEW statement with SELECT query as body.
+    /// Query can't have bound parameters.
+    pub fn create_view<S: ToString>(name: S, query: &Sqlite3Builder) -> CreateView {
+        CreateView::new(name, query)
+    }
+
+    /// Create DROP VIEW statement.
+    pub fn drop_view<S: ToString>(name: S) -> DropStatement {
+        DropStatement::new("VIEW", name.to_string())
+    }
+
+    /// Create CREATE TRIGGER statement on table or view.
+    /// Body statements can't have bound parameters.
+    pub fn create_trigger<S: ToString, T: ToString>(name: S, table: T) -> CreateTrigger {
+        CreateTrigger::new(name, table)
+    }
+
+    /// Create DROP TRIGGER statement.
+    pub fn drop_trigger<S: ToString>(name: S) -> DropStatement {
+        DropStatement::new("TRIGGER", name.to_string())
+    }
+
+    /// Create ALTER TABLE statements.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::alter_table("books")
+    ///     .rename_column("price", "cost")
+    ///     .sql()?;
+    ///
+    /// assert_eq!("ALTER TABLE books RENAME COLUMN price TO cost;", &sql);
+    /// // add                  ^^^^^
+    /// // here                 table
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn alter_table<S: ToString>(table: S) -> AlterTable {
+        AlterTable::new(table)
+    }
+
+    /// Create rebuild of table to new definition for changes ALTER TABLE can't do,
+    /// like changing column type, constraint or default.
+    pub fn rebuild_table(table: &CreateTable) -> TableRebuild {
+        TableRebuild::new(table)
     }
 
     /// Use NATURAL JOIN
@@ -479,6 +835,8 @@ impl Sqlite3Builder {
     /// ```
     pub fn fields<S: ToString>(&mut self, fields: &[S]) -> &mut Self {
         self.builder.fields(fields);
+        self.fields
+            .extend(fields.iter().map(|field| field.to_string()));
         self
     }
 
@@ -531,6 +889,7 @@ impl Sqlite3Builder {
     /// ```
     pub fn set_fields<S: ToString>(&mut self, fields: &[S]) -> &mut Self {
         self.builder.set_fields(fields);
+        self.fields = fields.iter().map(|field| field.to_string()).collect();
         self
     }
 
@@ -555,7 +914,9 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn field<S: ToString>(&mut self, field: S) -> &mut Self {
-        self.builder.field(field);
+        let field = field.to_string();
+        self.builder.field(&field);
+        self.fields.push(field);
         self
     }
 
@@ -609,7 +970,9 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn set_field<S: ToString>(&mut self, field: S) -> &mut Self {
-        self.builder.set_field(field);
+        let field = field.to_string();
+        self.builder.set_field(&field);
+        self.fields = vec![field];
         self
     }
 
@@ -697,7 +1060,32 @@ impl Sqlite3Builder {
         self
     }
 
-    /// Add SELECT part (for INSERT).
+    /// Set conflict resolution algorithm (for INSERT and UPDATE).
+    fn or(&mut self, algorithm: &'static str) -> &mut Self {
+        self.conflict = Some(algorithm);
+        self
+    }
+
+    /// Use INSERT OR ROLLBACK or UPDATE OR ROLLBACK.
+    /// Constraint violation rolls back the whole transaction.
+    pub fn or_rollback(&mut self) -> &mut Self {
+        self.or("ROLLBACK")
+    }
+
+    /// Use INSERT OR ABORT or UPDATE OR ABORT.
+    /// Constraint violation reverts the statement, default behavior of SQLite.
+    pub fn or_abort(&mut self) -> &mut Self {
+        self.or("ABORT")
+    }
+
+    /// Use INSERT OR FAIL or UPDATE OR FAIL.
+    /// Constraint violation stops the statement keeping changes made before.
+    pub fn or_fail(&mut self) -> &mut Self {
+        self.or("FAIL")
+    }
+
+    /// Use INSERT OR IGNORE or UPDATE OR IGNORE.
+    /// Rows violating constraints are skipped.
     ///
     /// ```
     /// extern crate sqlite3builder;
@@ -706,31 +1094,24 @@ impl Sqlite3Builder {
     /// use sqlite3builder::Sqlite3Builder;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let query = Sqlite3Builder::select_from("warehouse")
-    ///     .field("title")
-    ///     .field("preliminary_price * 2")
-    ///     .query()?;
-    ///
-    /// assert_eq!("SELECT title, preliminary_price * 2 FROM warehouse", &query);
-    ///
     /// let sql = Sqlite3Builder::insert_into("books")
     ///     .field("title")
-    ///     .field("price")
-    ///     .select(&query)
+    ///     .values(&["'Dune'"])
+    ///     .or_ignore()
     ///     .sql()?;
     ///
-    /// assert_eq!("INSERT INTO books (title, price) SELECT title, preliminary_price * 2 FROM warehouse;", &sql);
-    /// // add                                       ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
-    /// // here                                                            query
+    /// assert_eq!("INSERT OR IGNORE INTO books (title) VALUES ('Dune');", &sql);
+    /// // add                ^^^^^^
+    /// // here             algorithm
     /// # Ok(())
     /// # }
     /// ```
-    pub fn select<S: ToString>(&mut self, query: S) -> &mut Self {
-        self.builder.select(query);
-        self
+    pub fn or_ignore(&mut self) -> &mut Self {
+        self.or("IGNORE")
     }
 
-    /// Add GROUP BY part.
+    /// Use INSERT OR REPLACE or UPDATE OR REPLACE.
+    /// Rows conflicting by unique constraint are deleted before the change.
     ///
     /// ```
     /// extern crate sqlite3builder;
@@ -739,138 +1120,147 @@ impl Sqlite3Builder {
     /// use sqlite3builder::Sqlite3Builder;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let sql = Sqlite3Builder::select_from("books")
-    ///     .field("price")
-    ///     .field("COUNT(price) AS cnt")
-    ///     .group_by("price")
-    ///     .order_desc("cnt")
+    /// let sql = Sqlite3Builder::update_table("books")
+    ///     .set("isbn", "'9780441013593'")
+    ///     .and_where("title = 'Dune'")
+    ///     .or_replace()
     ///     .sql()?;
     ///
-    /// assert_eq!("SELECT price, COUNT(price) AS cnt FROM books GROUP BY price ORDER BY cnt DESC;", &sql);
-    /// // add                                                            ^^^^^
-    /// // here                                                           field
+    /// assert_eq!("UPDATE OR REPLACE books SET isbn = '9780441013593' WHERE title = 'Dune';", &sql);
+    /// // add                ^^^^^^^
+    /// // here              algorithm
     /// # Ok(())
     /// # }
     /// ```
-    pub fn group_by<S: ToString>(&mut self, field: S) -> &mut Self {
-        self.builder.group_by(field);
-        self
+    pub fn or_replace(&mut self) -> &mut Self {
+        self.or("REPLACE")
     }
 
-    /// Add HAVING condition.
+    /// Add ON CONFLICT clause with conflict target (for INSERT).
+    /// Empty target is allowed with `do_nothing` only.
+    /// Every call starts new clause, as SQLite 3.35.0 allows several of them.
+    /// INSERT from SELECT without WHERE gets `WHERE true` to avoid parsing ambiguity.
     ///
     /// ```
     /// extern crate sqlite3builder;
     ///
     /// # use std::error::Error;
-    /// use sqlite3builder::{Sqlite3Builder, quote};
+    /// use sqlite3builder::Sqlite3Builder;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let sql = Sqlite3Builder::select_from("books")
-    ///     .field("price")
-    ///     .field("COUNT(price) AS cnt")
-    ///     .group_by("price")
-    ///     .having("price > 100")
-    ///     .order_desc("cnt")
+    /// let sql = Sqlite3Builder::insert_into("books")
+    ///     .fields(&["isbn", "title", "price"])
+    ///     .values(&["'9780441013593', 'Dune', 120"])
+    ///     .on_conflict(&["isbn"])
+    ///     .do_update_all()
+    ///     .do_update_where("excluded.price <> books.price")
     ///     .sql()?;
     ///
-    /// assert_eq!("SELECT price, COUNT(price) AS cnt FROM books GROUP BY price HAVING price > 100 ORDER BY cnt DESC;", &sql);
-    /// // add                                                                         ^^^^^^^^^^^
-    /// // here                                                                           cond
+    /// assert_eq!("INSERT INTO books (isbn, title, price) VALUES ('9780441013593', 'Dune', 120) \
+    ///     ON CONFLICT (isbn) DO UPDATE SET title = excluded.title, price = excluded.price \
+    ///     WHERE excluded.price <> books.price;", &sql);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn having<S: ToString>(&mut self, cond: S) -> &mut Self {
-        self.builder.having(cond);
+    pub fn on_conflict<S: ToString>(&mut self, target: &[S]) -> &mut Self {
+        let target = target.iter().map(|field| field.to_string()).collect();
+        self.upserts.push(OnConflict::new(target));
         self
     }
 
-    /// Add WHERE condition.
+    /// Last ON CONFLICT clause, created without target if missing
+    fn last_upsert(&mut self) -> &mut OnConflict {
+        if self.upserts.is_empty() {
+            self.upserts.push(OnConflict::new(Vec::new()));
+        }
+        self.upserts.last_mut().unwrap()
+    }
+
+    /// Add WHERE condition of partial unique index to conflict target.
     ///
     /// ```
     /// extern crate sqlite3builder;
     ///
     /// # use std::error::Error;
-    /// use sqlite3builder::{Sqlite3Builder, quote};
+    /// use sqlite3builder::Sqlite3Builder;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let sql = Sqlite3Builder::select_from("books")
-    ///     .field("title")
-    ///     .field("price")
-    ///     .and_where("price > 100")
-    ///     .and_where("title LIKE 'Harry Potter%'")
+    /// let sql = Sqlite3Builder::insert_into("books")
+    ///     .fields(&["title", "active"])
+    ///     .values(&["'Dune', 1"])
+    ///     .on_conflict(&["title"])
+    ///     .on_conflict_where("active")
+    ///     .do_nothing()
     ///     .sql()?;
     ///
-    /// assert_eq!("SELECT title, price FROM books WHERE (price > 100) AND (title LIKE 'Harry Potter%');", &sql);
-    /// // add                                            ^^^^^^^^^^^       ^^^^^^^^^^^^^^^^^^^^^^^^^^
-    /// // here                                              cond                      cond
+    /// assert_eq!("INSERT INTO books (title, active) VALUES ('Dune', 1) ON CONFLICT (title) WHERE active DO NOTHING;", &sql);
+    /// // add                                                                                     ^^^^^^
+    /// // here                                                                                   condition
     /// # Ok(())
     /// # }
     /// ```
-    pub fn and_where<S: ToString>(&mut self, cond: S) -> &mut Self {
-        self.builder.and_where(cond);
+    pub fn on_conflict_where<S: ToString>(&mut self, cond: S) -> &mut Self {
+        self.last_upsert().target_where(cond.to_string());
         self
     }
 
-    /// Add WHERE condition for equal parts.
+    /// Ignore conflicting row.
     ///
     /// ```
     /// extern crate sqlite3builder;
     ///
     /// # use std::error::Error;
-    /// use sqlite3builder::{Sqlite3Builder, quote};
+    /// use sqlite3builder::Sqlite3Builder;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let sql = Sqlite3Builder::select_from("books")
-    ///     .field("price")
-    ///     .and_where_eq("title", &quote("Harry Potter and the Philosopher's Stone"))
+    /// let sql = Sqlite3Builder::insert_into("books")
+    ///     .field("title")
+    ///     .values(&["'Dune'"])
+    ///     .do_nothing()
     ///     .sql()?;
     ///
-    /// assert_eq!("SELECT price FROM books WHERE title = 'Harry Potter and the Philosopher''s Stone';", &sql);
-    /// // add                                    ^^^^^   ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
-    /// // here                                   field                      value
+    /// assert_eq!("INSERT INTO books (title) VALUES ('Dune') ON CONFLICT DO NOTHING;", &sql);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn and_where_eq<S, T>(&mut self, field: S, value: T) -> &mut Self
-    where
-        S: ToString,
-        T: ToString,
-    {
-        self.builder.and_where_eq(field, value);
+    pub fn do_nothing(&mut self) -> &mut Self {
+        self.last_upsert().do_nothing();
         self
     }
 
-    /// Add WHERE condition for non-equal parts.
+    /// Update field of conflicting row.
+    /// Inserted values are available as `excluded.field`.
     ///
     /// ```
     /// extern crate sqlite3builder;
     ///
     /// # use std::error::Error;
-    /// use sqlite3builder::{Sqlite3Builder, quote};
+    /// use sqlite3builder::Sqlite3Builder;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let sql = Sqlite3Builder::select_from("books")
-    ///     .field("price")
-    ///     .and_where_ne("title", &quote("Harry Potter and the Philosopher's Stone"))
+    /// let sql = Sqlite3Builder::insert_into("stock")
+    ///     .fields(&["isbn", "amount"])
+    ///     .values(&["'9780441013593', 5"])
+    ///     .on_conflict(&["isbn"])
+    ///     .do_update_set("amount", "amount + excluded.amount")
     ///     .sql()?;
     ///
-    /// assert_eq!("SELECT price FROM books WHERE title <> 'Harry Potter and the Philosopher''s Stone';", &sql);
-    /// // add                                    ^^^^^    ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
-    /// // here                                   field                       value
+    /// assert_eq!("INSERT INTO stock (isbn, amount) VALUES ('9780441013593', 5) ON CONFLICT (isbn) DO UPDATE SET amount = amount + excluded.amount;", &sql);
+    /// // add                                                                                                    ^^^^^^   ^^^^^^^^^^^^^^^^^^^^^^^^
+    /// // here                                                                                                   field              value
     /// # Ok(())
     /// # }
     /// ```
-    pub fn and_where_ne<S, T>(&mut self, field: S, value: T) -> &mut Self
+    pub fn do_update_set<S, T>(&mut self, field: S, value: T) -> &mut Self
     where
         S: ToString,
         T: ToString,
     {
-        self.builder.and_where_ne(field, value);
+        self.last_upsert().set(field.to_string(), value.to_string());
         self
     }
 
-    /// Add WHERE condition for field greater than value.
+    /// Update fields of conflicting row with inserted values.
     ///
     /// ```
     /// extern crate sqlite3builder;
@@ -879,23 +1269,238 @@ impl Sqlite3Builder {
     /// use sqlite3builder::Sqlite3Builder;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let sql = Sqlite3Builder::select_from("books")
-    ///     .field("title")
-    ///     .field("price")
-    ///     .and_where_gt("price", 300.to_string())
+    /// let sql = Sqlite3Builder::insert_into("books")
+    ///     .fields(&["isbn", "title", "price"])
+    ///     .values(&["'9780441013593', 'Dune', 120"])
+    ///     .on_conflict(&["isbn"])
+    ///     .do_update_excluded(&["price"])
     ///     .sql()?;
     ///
-    /// assert_eq!("SELECT title, price FROM books WHERE price > 300;", &sql);
-    /// // add                                           ^^^^^   ^^^
-    /// // here                                          field  value
+    /// assert_eq!("INSERT INTO books (isbn, title, price) VALUES ('9780441013593', 'Dune', 120) ON CONFLICT (isbn) DO UPDATE SET price = excluded.price;", &sql);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn and_where_gt<S, T>(&mut self, field: S, value: T) -> &mut Self
-    where
-        S: ToString,
-        T: ToString,
-    {
+    pub fn do_update_excluded<S: ToString>(&mut self, fields: &[S]) -> &mut Self {
+        let upsert = self.last_upsert();
+        for field in fields {
+            let field = field.to_string();
+            upsert.set(field.clone(), format!("excluded.{}", field));
+        }
+        self
+    }
+
+    /// Update all inserted fields except conflict target with inserted values.
+    /// Fields are taken from `field` and `fields` calls.
+    pub fn do_update_all(&mut self) -> &mut Self {
+        self.last_upsert().update_all();
+        self
+    }
+
+    /// Add WHERE condition to DO UPDATE, so conflicting rows not matching it are kept.
+    pub fn do_update_where<S: ToString>(&mut self, cond: S) -> &mut Self {
+        self.last_upsert().update_where(cond.to_string());
+        self
+    }
+
+    /// Add SELECT part (for INSERT).
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let query = Sqlite3Builder::select_from("warehouse")
+    ///     .field("title")
+    ///     .field("preliminary_price * 2")
+    ///     .query()?;
+    ///
+    /// assert_eq!("SELECT title, preliminary_price * 2 FROM warehouse", &query);
+    ///
+    /// let sql = Sqlite3Builder::insert_into("books")
+    ///     .field("title")
+    ///     .field("price")
+    ///     .select(&query)
+    ///     .sql()?;
+    ///
+    /// assert_eq!("INSERT INTO books (title, price) SELECT title, preliminary_price * 2 FROM warehouse;", &sql);
+    /// // add                                       ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+    /// // here                                                            query
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn select<S: ToString>(&mut self, query: S) -> &mut Self {
+        self.builder.select(query);
+        self
+    }
+
+    /// Add GROUP BY part.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::select_from("books")
+    ///     .field("price")
+    ///     .field("COUNT(price) AS cnt")
+    ///     .group_by("price")
+    ///     .order_desc("cnt")
+    ///     .sql()?;
+    ///
+    /// assert_eq!("SELECT price, COUNT(price) AS cnt FROM books GROUP BY price ORDER BY cnt DESC;", &sql);
+    /// // add                                                            ^^^^^
+    /// // here                                                           field
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn group_by<S: ToString>(&mut self, field: S) -> &mut Self {
+        self.builder.group_by(field);
+        self
+    }
+
+    /// Add HAVING condition.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::{Sqlite3Builder, quote};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::select_from("books")
+    ///     .field("price")
+    ///     .field("COUNT(price) AS cnt")
+    ///     .group_by("price")
+    ///     .having("price > 100")
+    ///     .order_desc("cnt")
+    ///     .sql()?;
+    ///
+    /// assert_eq!("SELECT price, COUNT(price) AS cnt FROM books GROUP BY price HAVING price > 100 ORDER BY cnt DESC;", &sql);
+    /// // add                                                                         ^^^^^^^^^^^
+    /// // here                                                                           cond
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn having<S: ToString>(&mut self, cond: S) -> &mut Self {
+        self.builder.having(cond);
+        self
+    }
+
+    /// Add WHERE condition.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::{Sqlite3Builder, quote};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::select_from("books")
+    ///     .field("title")
+    ///     .field("price")
+    ///     .and_where("price > 100")
+    ///     .and_where("title LIKE 'Harry Potter%'")
+    ///     .sql()?;
+    ///
+    /// assert_eq!("SELECT title, price FROM books WHERE (price > 100) AND (title LIKE 'Harry Potter%');", &sql);
+    /// // add                                            ^^^^^^^^^^^       ^^^^^^^^^^^^^^^^^^^^^^^^^^
+    /// // here                                              cond                      cond
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn and_where<S: ToString>(&mut self, cond: S) -> &mut Self {
+        self.builder.and_where(cond);
+        self
+    }
+
+    /// Add WHERE condition for equal parts.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::{Sqlite3Builder, quote};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::select_from("books")
+    ///     .field("price")
+    ///     .and_where_eq("title", &quote("Harry Potter and the Philosopher's Stone"))
+    ///     .sql()?;
+    ///
+    /// assert_eq!("SELECT price FROM books WHERE title = 'Harry Potter and the Philosopher''s Stone';", &sql);
+    /// // add                                    ^^^^^   ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+    /// // here                                   field                      value
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn and_where_eq<S, T>(&mut self, field: S, value: T) -> &mut Self
+    where
+        S: ToString,
+        T: ToString,
+    {
+        self.builder.and_where_eq(field, value);
+        self
+    }
+
+    /// Add WHERE condition for non-equal parts.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::{Sqlite3Builder, quote};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::select_from("books")
+    ///     .field("price")
+    ///     .and_where_ne("title", &quote("Harry Potter and the Philosopher's Stone"))
+    ///     .sql()?;
+    ///
+    /// assert_eq!("SELECT price FROM books WHERE title <> 'Harry Potter and the Philosopher''s Stone';", &sql);
+    /// // add                                    ^^^^^    ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+    /// // here                                   field                       value
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn and_where_ne<S, T>(&mut self, field: S, value: T) -> &mut Self
+    where
+        S: ToString,
+        T: ToString,
+    {
+        self.builder.and_where_ne(field, value);
+        self
+    }
+
+    /// Add WHERE condition for field greater than value.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::select_from("books")
+    ///     .field("title")
+    ///     .field("price")
+    ///     .and_where_gt("price", 300.to_string())
+    ///     .sql()?;
+    ///
+    /// assert_eq!("SELECT title, price FROM books WHERE price > 300;", &sql);
+    /// // add                                           ^^^^^   ^^^
+    /// // here                                          field  value
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn and_where_gt<S, T>(&mut self, field: S, value: T) -> &mut Self
+    where
+        S: ToString,
+        T: ToString,
+    {
         self.builder.and_where_gt(field, value);
         self
     }
@@ -1775,6 +2380,34 @@ impl Sqlite3Builder {
         self
     }
 
+    /// Add named window definition to WINDOW clause.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::{aggregate, row_number, Sqlite3Builder, Window};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::select_from("books")
+    ///     .field("title")
+    ///     .field(row_number().over_named("w").alias("pos"))
+    ///     .field(aggregate("AVG(price)").over_named("w"))
+    ///     .window("w", Window::new().partition_by("author").order_desc("price"))
+    ///     .order_asc("title")
+    ///     .sql()?;
+    ///
+    /// assert_eq!("SELECT title, row_number() OVER w AS pos, AVG(price) OVER w FROM books \
+    ///     WINDOW w AS (PARTITION BY author ORDER BY price DESC) ORDER BY title;", &sql);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn window<S: ToString>(&mut self, name: S, window: &Window) -> &mut Self {
+        self.windows
+            .push(format!("{} AS {}", name.to_string(), window));
+        self
+    }
+
     /// Union query with subquery.
     /// ORDER BY must be in the last subquery.
     ///
@@ -1807,7 +2440,7 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn union<S: ToString>(&mut self, query: S) -> &mut Self {
-        self.builder.union(query);
+        self.tail.union("UNION", query.to_string());
         self
     }
 
@@ -1839,7 +2472,7 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn union_all<S: ToString>(&mut self, query: S) -> &mut Self {
-        self.builder.union_all(query);
+        self.tail.union("UNION ALL", query.to_string());
         self
     }
 
@@ -1866,7 +2499,7 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn order_by<S: ToString>(&mut self, field: S, desc: bool) -> &mut Self {
-        self.builder.order_by(field, desc);
+        self.tail.order_by(field.to_string(), desc);
         self
     }
 
@@ -1893,8 +2526,7 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn order_asc<S: ToString>(&mut self, field: S) -> &mut Self {
-        self.builder.order_asc(field);
-        self
+        self.order_by(field, false)
     }
 
     /// Add ORDER BY DESC.
@@ -1920,8 +2552,7 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn order_desc<S: ToString>(&mut self, field: S) -> &mut Self {
-        self.builder.order_desc(field);
-        self
+        self.order_by(field, true)
     }
 
     /// Set LIMIT.
@@ -1948,7 +2579,7 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn limit<S: ToString>(&mut self, limit: S) -> &mut Self {
-        self.builder.limit(limit);
+        self.tail.limit(limit.to_string());
         self
     }
 
@@ -1977,11 +2608,12 @@ impl Sqlite3Builder {
     /// # }
     /// ```
     pub fn offset<S: ToString>(&mut self, offset: S) -> &mut Self {
-        self.builder.offset(offset);
+        self.tail.offset(offset.to_string());
         self
     }
 
-    /// Build complete SQL command.
+    /// Add RETURNING clause to INSERT, UPDATE or DELETE.
+    /// Requires SQLite 3.35.0 or later.
     ///
     /// ```
     /// extern crate sqlite3builder;
@@ -1990,48 +2622,71 @@ impl Sqlite3Builder {
     /// use sqlite3builder::Sqlite3Builder;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let sql = Sqlite3Builder::select_from("books").sql()?;
+    /// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+    /// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+    /// # let conn = pool.get()?;
+    /// # conn.execute("CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT, price INTEGER);")?;
+    /// let mut db = Sqlite3Builder::insert_into("books");
+    /// db.field("title")
+    ///     .field("price")
+    ///     .values(&["'Dune', 120"])
+    ///     .returning(&["id", "price * 2"]);
     ///
-    /// assert_eq!("SELECT * FROM books;", &sql);
+    /// assert_eq!("INSERT INTO books (title, price) VALUES ('Dune', 120) RETURNING id, price * 2;", &db.sql()?);
+    /// // add                                                                      ^^^^^^^^^^^^^
+    /// // here                                                                        fields
+    ///
+    /// let (id, double) = db.get_one_as::<(i64, i64)>(&conn)?;
+    ///
+    /// assert_eq!((1, 240), (id, double));
     /// # Ok(())
     /// # }
     /// ```
-    pub fn sql(&self) -> Result<String, Box<dyn Error>> {
-        self.builder.sql()
+    pub fn returning<S: ToString>(&mut self, fields: &[S]) -> &mut Self {
+        self.returning
+            .extend(fields.iter().map(|field| field.to_string()));
+        self
     }
 
-    /// Build subquery SQL command.
+    /// Add bind parameter and return its numbered placeholder `?NNN`.
+    /// The value is not pasted into the SQL text, so it needs no quoting.
+    /// LIKE helpers quote their mask, so use `and_where` for parameterized LIKE.
     ///
     /// ```
     /// extern crate sqlite3builder;
     ///
     /// # use std::error::Error;
     /// use sqlite3builder::Sqlite3Builder;
+    /// use sqlite3::Value;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let cat = Sqlite3Builder::select_from("books")
-    ///     .field("CASE WHEN price < 100 THEN 'cheap' ELSE 'expensive' END AS category")
-    ///     .subquery()?;
-    ///
-    /// assert_eq!("(SELECT CASE WHEN price < 100 THEN 'cheap' ELSE 'expensive' END AS category FROM books)", &cat);
+    /// let mut db = Sqlite3Builder::select_from("books");
+    /// let title = db.param("Harry Potter and the Philosopher's Stone");
+    /// let price = db.param(100);
     ///
-    /// let sql = Sqlite3Builder::select_from(&cat)
-    ///     .field("category")
-    ///     .field("COUNT(category) AS cnt")
-    ///     .group_by("category")
-    ///     .order_desc("cnt")
-    ///     .order_asc("category")
+    /// let sql = db
+    ///     .field("price")
+    ///     .and_where_eq("title", &title)
+    ///     .and_where_gt("price", &price)
     ///     .sql()?;
     ///
-    /// assert_eq!("SELECT category, COUNT(category) AS cnt FROM (SELECT CASE WHEN price < 100 THEN 'cheap' ELSE 'expensive' END AS category FROM books) GROUP BY category ORDER BY cnt DESC, category;", &sql);
+    /// assert_eq!("SELECT price FROM books WHERE (title = ?1) AND (price > ?2);", &sql);
+    /// // add                                            ^^              ^^
+    /// // here                                          param           param
+    /// assert_eq!(db.params(), &[
+    ///     Value::String("Harry Potter and the Philosopher's Stone".to_string()),
+    ///     Value::Integer(100),
+    /// ]);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn subquery(&self) -> Result<String, Box<dyn Error>> {
-        self.builder.subquery()
+    pub fn param<T: ToValue>(&mut self, value: T) -> String {
+        self.params.push(value.to_value());
+        format!("?{}", self.params.len())
     }
 
-    /// Build named subquery SQL command.
+    /// Add bind parameter for the next anonymous `?` placeholder.
+    /// Values must be added in the order of placeholders in the SQL text.
     ///
     /// ```
     /// extern crate sqlite3builder;
@@ -2040,10 +2695,220 @@ impl Sqlite3Builder {
     /// use sqlite3builder::Sqlite3Builder;
     ///
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// let cat = Sqlite3Builder::select_from("books")
-    ///     .field("CASE WHEN price < 100 THEN 'cheap' ELSE 'expensive' END")
-    ///     .subquery_as("category")?;
-    ///
+    /// let sql = Sqlite3Builder::insert_into("books")
+    ///     .field("title")
+    ///     .field("price")
+    ///     .values(&["?", "?"])
+    ///     .bind("Don Quixote")
+    ///     .bind(200)
+    ///     .sql()?;
+    ///
+    /// assert_eq!("INSERT INTO books (title, price) VALUES (?, ?);", &sql);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bind<T: ToValue>(&mut self, value: T) -> &mut Self {
+        self.params.push(value.to_value());
+        self
+    }
+
+    /// Bind parameters in placeholder order.
+    pub fn params(&self) -> &[SValue] {
+        &self.params
+    }
+
+    /// Build complete SQL command.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let sql = Sqlite3Builder::select_from("books").sql()?;
+    ///
+    /// assert_eq!("SELECT * FROM books;", &sql);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sql(&self) -> Result<String, Error> {
+        let sql = self
+            .builder
+            .sql()
+            .map_err(|err| Error::Build(err.to_string()))?;
+        let sql = if self.kind == Kind::Select {
+            if !self.returning.is_empty() {
+                return Err(Error::Build(
+                    "RETURNING requires INSERT, UPDATE or DELETE".to_string(),
+                ));
+            }
+            let query = sql.strip_suffix(';').unwrap_or(&sql);
+            self.select_tail(query.to_string()) + ";"
+        } else {
+            self.dml_tail(sql)?
+        };
+        Ok(self.with_clause()? + &sql)
+    }
+
+    /// Query is SELECT
+    pub(crate) fn is_select(&self) -> bool {
+        self.kind == Kind::Select
+    }
+
+    /// Query has WITH or RETURNING clause
+    pub(crate) fn has_with_or_returning(&self) -> bool {
+        !self.ctes.is_empty() || !self.returning.is_empty()
+    }
+
+    /// WHERE clause of query over table, e.g. for partial index
+    pub(crate) fn where_clause(&self, table: &str) -> Result<String, Error> {
+        if self.kind != Kind::Select
+            || !self.ctes.is_empty()
+            || !self.windows.is_empty()
+            || !self.tail.is_empty()
+        {
+            return Err(Error::Build(format!(
+                "Only WHERE conditions of SELECT from {} are allowed",
+                table
+            )));
+        }
+        if !self.params.is_empty() {
+            return Err(Error::Build(
+                "Parameters are not allowed in WHERE of schema object".to_string(),
+            ));
+        }
+        let query = self
+            .builder
+            .query()
+            .map_err(|err| Error::Build(err.to_string()))?;
+        match query.strip_prefix(&format!("SELECT * FROM {}", table)) {
+            Some(wheres) if wheres.is_empty() || wheres.starts_with(" WHERE ") => {
+                Ok(wheres.to_string())
+            }
+            _ => Err(Error::Build(format!(
+                "Only WHERE conditions of SELECT from {} are allowed",
+                table
+            ))),
+        }
+    }
+
+    /// Add WINDOW clause, unions, ORDER BY, LIMIT and OFFSET to SELECT
+    fn select_tail(&self, query: String) -> String {
+        if self.windows.is_empty() {
+            format!("{}{}", query, self.tail)
+        } else {
+            format!("{} WINDOW {}{}", query, self.windows.join(", "), self.tail)
+        }
+    }
+
+    /// Build WITH clause followed by space
+    fn with_clause(&self) -> Result<String, Error> {
+        if self.ctes.iter().any(Cte::has_hint) {
+            require_sqlite(3_035_000, "MATERIALIZED hint")?;
+        }
+        cte::with_clause(&self.ctes, self.recursive)
+    }
+
+    /// Add conflict algorithm and clauses which follow INSERT, UPDATE or DELETE body
+    fn dml_tail(&self, sql: String) -> Result<String, Error> {
+        if !self.windows.is_empty() {
+            return Err(Error::Build("WINDOW requires SELECT".to_string()));
+        }
+        if !self.tail.is_empty() {
+            return Err(Error::Build(
+                "UNION, ORDER BY, LIMIT and OFFSET require SELECT".to_string(),
+            ));
+        }
+        let mut text = sql.strip_suffix(';').unwrap_or(&sql).to_string();
+        if let Some(algorithm) = self.conflict {
+            let verb = match self.kind {
+                Kind::Insert => "INSERT",
+                Kind::Update => "UPDATE",
+                _ => {
+                    return Err(Error::Build(format!(
+                        "OR {} requires INSERT or UPDATE",
+                        algorithm
+                    )))
+                }
+            };
+            text.insert_str(verb.len(), &format!(" OR {}", algorithm));
+        }
+        if !self.upserts.is_empty() {
+            if self.kind != Kind::Insert {
+                return Err(Error::Build("ON CONFLICT requires INSERT".to_string()));
+            }
+            require_sqlite(3_024_000, "UPSERT")?;
+            if self.upserts.len() > 1 {
+                require_sqlite(3_035_000, "Multiple ON CONFLICT clauses")?;
+            }
+            if upsert::select_needs_where(&text) {
+                text.push_str(" WHERE true");
+            }
+            let columns = self
+                .fields
+                .iter()
+                .flat_map(|field| field.split(','))
+                .map(|field| field.trim().to_string())
+                .collect::<Vec<String>>();
+            for upsert in &self.upserts {
+                text.push(' ');
+                text.push_str(&upsert.sql(&columns)?);
+            }
+        }
+        if !self.returning.is_empty() {
+            require_sqlite(3_035_000, "RETURNING")?;
+            text.push_str(" RETURNING ");
+            text.push_str(&self.returning.join(", "));
+        }
+        text.push(';');
+        Ok(text)
+    }
+
+    /// Build subquery SQL command.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let cat = Sqlite3Builder::select_from("books")
+    ///     .field("CASE WHEN price < 100 THEN 'cheap' ELSE 'expensive' END AS category")
+    ///     .subquery()?;
+    ///
+    /// assert_eq!("(SELECT CASE WHEN price < 100 THEN 'cheap' ELSE 'expensive' END AS category FROM books)", &cat);
+    ///
+    /// let sql = Sqlite3Builder::select_from(&cat)
+    ///     .field("category")
+    ///     .field("COUNT(category) AS cnt")
+    ///     .group_by("category")
+    ///     .order_desc("cnt")
+    ///     .order_asc("category")
+    ///     .sql()?;
+    ///
+    /// assert_eq!("SELECT category, COUNT(category) AS cnt FROM (SELECT CASE WHEN price < 100 THEN 'cheap' ELSE 'expensive' END AS category FROM books) GROUP BY category ORDER BY cnt DESC, category;", &sql);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subquery(&self) -> Result<String, Error> {
+        Ok(format!("({})", self.query()?))
+    }
+
+    /// Build named subquery SQL command.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let cat = Sqlite3Builder::select_from("books")
+    ///     .field("CASE WHEN price < 100 THEN 'cheap' ELSE 'expensive' END")
+    ///     .subquery_as("category")?;
+    ///
     /// assert_eq!("(SELECT CASE WHEN price < 100 THEN 'cheap' ELSE 'expensive' END FROM books) AS category", &cat);
     /// // add                                                                                     ^^^^^^^^
     /// // here                                                                                      name
@@ -2058,8 +2923,8 @@ impl Sqlite3Builder {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn subquery_as<S: ToString>(&self, name: S) -> Result<String, Box<dyn Error>> {
-        self.builder.subquery_as(name)
+    pub fn subquery_as<S: ToString>(&self, name: S) -> Result<String, Error> {
+        Ok(format!("({}) AS {}", self.query()?, name.to_string()))
     }
 
     /// SQL command generator for query or subquery.
@@ -2088,8 +2953,13 @@ impl Sqlite3Builder {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn query(&self) -> Result<String, Box<dyn Error>> {
-        self.builder.query()
+    pub fn query(&self) -> Result<String, Error> {
+        self.check_embedded()?;
+        let query = self
+            .builder
+            .query()
+            .map_err(|err| Error::Build(err.to_string()))?;
+        Ok(self.with_clause()? + &self.select_tail(query))
     }
 
     /// SQL command generator for query or subquery without a table.
@@ -2108,90 +2978,557 @@ impl Sqlite3Builder {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn query_values(&self) -> Result<String, Box<dyn Error>> {
-        self.builder.query_values()
+    pub fn query_values(&self) -> Result<String, Error> {
+        self.check_embedded()?;
+        let query = self
+            .builder
+            .query_values()
+            .map_err(|err| Error::Build(err.to_string()))?;
+        Ok(self.select_tail(query))
+    }
+
+    /// Set BLOB representation for JSON results.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// use sqlite3builder::{BlobEncoding, Sqlite3Builder};
+    ///
+    /// let mut db = Sqlite3Builder::select_from("files");
+    /// db.field("content").blob_encoding(BlobEncoding::Hex);
+    /// ```
+    pub fn blob_encoding(&mut self, encoding: BlobEncoding) -> &mut Self {
+        self.json.blob = encoding;
+        self
+    }
+
+    /// Set NaN and infinity representation for JSON results.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// use sqlite3builder::{NonFinite, Sqlite3Builder};
+    ///
+    /// let mut db = Sqlite3Builder::select_from("measures");
+    /// db.field("value").non_finite(NonFinite::Error);
+    /// ```
+    pub fn non_finite(&mut self, policy: NonFinite) -> &mut Self {
+        self.json.non_finite = policy;
+        self
+    }
+
+    /// Convert scalar results of other storage class following SQLite affinity rules.
+    /// Disabled by default.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let conn = sqlite3::open(":memory:")?;
+    ///
+    /// let mut db = Sqlite3Builder::select_values(&["' 42'"]);
+    /// assert!(db.get_i64(&conn).is_err());
+    ///
+    /// let answer = db.coerce_types(true).get_i64(&conn)?;
+    /// assert_eq!(42, answer);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn coerce_types(&mut self, enable: bool) -> &mut Self {
+        self.coerce = enable;
+        self
     }
 
     /// Convert sqlite3::Value to serde_json::Value
-    fn s2j(src: &SValue) -> Result<JValue, Box<dyn Error>> {
-        match src {
-            SValue::Null => Ok(JValue::Null),
-            SValue::Integer(val) => Ok(JValue::Number((*val).into())),
-            SValue::String(val) => Ok(JValue::String(val.clone())),
-            _ => Err("Unsupported type".into()),
+    fn s2j(&self, src: &SValue) -> Result<JValue, Error> {
+        self.json.convert(src)
+    }
+
+    /// Convert row of sqlite3::Value to row of serde_json::Value
+    fn row2j(&self, row: &[SValue]) -> Result<Vec<JValue>, Error> {
+        row.iter().map(|val| self.s2j(val)).collect()
+    }
+
+    /// Check that query embedded as text has no bound parameters
+    fn check_embedded(&self) -> Result<(), Error> {
+        if !self.params.is_empty() {
+            return Err(Error::Build(
+                "Parameters of embedded query would be lost, bind them in outer builder"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Prepare statement and bind parameters
+    fn prepare<'a>(&self, conn: &'a impl Executor, sql: &str) -> Result<Statement<'a>, Error> {
+        check_single_statement(sql)?;
+        let mut stmt = conn.connection().prepare(sql)?;
+        self.bind_params(&mut stmt)?;
+        Ok(stmt)
+    }
+
+    /// Bind parameters to statement
+    fn bind_params(&self, stmt: &mut Statement) -> Result<(), Error> {
+        for (idx, param) in self.params.iter().enumerate() {
+            stmt.bind(idx + 1, param)?;
+        }
+        Ok(())
+    }
+
+    /// Run closure with statement of executor and bound parameters
+    fn run<T, F>(&self, conn: &impl Executor, sql: &str, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Statement) -> Result<T, Error>,
+    {
+        check_single_statement(sql)?;
+        conn.with_statement(sql, |stmt| {
+            self.bind_params(stmt)?;
+            f(stmt)
+        })
+    }
+
+    /// Step statement and read next row.
+    /// Column types are read for every row, unlike sqlite3::Cursor which keeps types of the first row.
+    fn next_row(stmt: &mut Statement) -> Result<Option<Vec<SValue>>, Error> {
+        if stmt.next()? == State::Done {
+            return Ok(None);
         }
+        let row = (0..stmt.columns())
+            .map(|idx| stmt.read::<SValue>(idx))
+            .collect::<Result<Vec<SValue>, _>>()?;
+        Ok(Some(row))
     }
 
-    /// Execute request
-    pub fn exec(&self, conn: &ConnPooled) -> Result<(), Box<dyn Error>> {
-        let sql = self.builder.sql()?;
+    /// Execute request and report affected rows.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+    /// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+    /// # let conn = pool.get()?;
+    /// # conn.execute("CREATE TABLE books (title TEXT, price INTEGER); INSERT INTO books VALUES ('Dune', 120), ('Emma', 80);")?;
+    /// let res = Sqlite3Builder::update_table("books")
+    ///     .set("price", "price + 10")
+    ///     .and_where("price > 100")
+    ///     .exec(&conn)?;
+    ///
+    /// assert_eq!(1, res.changes());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn exec(&self, conn: &impl Executor) -> Result<ExecResult, Error> {
+        let sql = self.sql()?;
         debug!("Exec sql = {}", &sql);
-        conn.execute(sql).map_err(|err| err.into())
+        self.run(conn, &sql, |stmt| {
+            while stmt.next()? != State::Done {}
+            Ok(())
+        })?;
+        conn.exec_result()
+    }
+
+    /// Execute INSERT and return rowid of inserted row.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+    /// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+    /// # let conn = pool.get()?;
+    /// # conn.execute("CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT);")?;
+    /// let mut db = Sqlite3Builder::insert_into("books");
+    /// let title = db.param("Dune");
+    /// let id = db.field("title").values(&[title]).insert_returning_id(&conn)?;
+    ///
+    /// assert_eq!(1, id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_returning_id(&self, conn: &impl Executor) -> Result<i64, Error> {
+        Ok(self.exec(conn)?.last_insert_rowid())
     }
 
     /// Execute and return all data
-    pub fn get(&self, conn: &ConnPooled) -> Result<Vec<Vec<JValue>>, Box<dyn Error>> {
-        let sql = self.builder.sql()?;
+    pub fn get(&self, conn: &impl Executor) -> Result<Vec<Vec<JValue>>, Error> {
+        let sql = self.sql()?;
         debug!("Get rows sql = {}", &sql);
-        let mut result = Vec::new();
-        let mut cursor = conn.prepare(sql)?.cursor();
-        while let Some(row) = cursor.next()? {
-            let jrow = row
-                .iter()
-                .map(|val| Self::s2j(&val).unwrap())
-                .collect::<Vec<JValue>>();
-            result.push(jrow);
-        }
-        Ok(result)
+        self.run(conn, &sql, |stmt| {
+            let mut result = Vec::new();
+            while let Some(row) = Self::next_row(stmt)? {
+                result.push(self.row2j(&row)?);
+            }
+            Ok(result)
+        })
     }
 
     /// Execute and return first row
-    pub fn get_row(&self, conn: &ConnPooled) -> Result<Vec<JValue>, Box<dyn Error>> {
-        let sql = self.builder.sql()?;
+    pub fn get_row(&self, conn: &impl Executor) -> Result<Vec<JValue>, Error> {
+        let sql = self.sql()?;
         debug!("Get row sql = {}", &sql);
-        let mut cursor = conn.prepare(sql)?.cursor();
-        let first_row = if let Some(row) = cursor.next()? {
-            row.iter()
-                .map(|val| Self::s2j(&val).unwrap())
-                .collect::<Vec<JValue>>()
-        } else {
-            Vec::new()
-        };
-        Ok(first_row)
+        self.run(conn, &sql, |stmt| {
+            let first_row = if let Some(row) = Self::next_row(stmt)? {
+                self.row2j(&row)?
+            } else {
+                Vec::new()
+            };
+            Ok(first_row)
+        })
+    }
+
+    /// Convert row to JSON object
+    fn row2obj(&self, keys: &[String], row: &[SValue]) -> Result<JMap<String, JValue>, Error> {
+        keys.iter()
+            .zip(row)
+            .map(|(key, val)| Ok((key.clone(), self.s2j(val)?)))
+            .collect()
+    }
+
+    /// Execute and return all data as JSON objects keyed by column names.
+    /// Repeated column names get suffix with occurrence number: `id`, `id_2`.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use serde_json::json;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+    /// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+    /// # let conn = pool.get()?;
+    /// # conn.execute("CREATE TABLE books (id INTEGER, title TEXT); INSERT INTO books VALUES (1, 'Dune');")?;
+    /// # conn.execute("CREATE TABLE shops (id INTEGER, book INTEGER); INSERT INTO shops VALUES (7, 1);")?;
+    /// let rows = Sqlite3Builder::select_from("books AS b")
+    ///     .field("b.id")
+    ///     .field("b.title")
+    ///     .field("s.id")
+    ///     .join("shops AS s")
+    ///     .on("b.id = s.book")
+    ///     .get_objects(&conn)?;
+    ///
+    /// assert_eq!(json!(rows), json!([{"id": 1, "title": "Dune", "id_2": 7}]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_objects(&self, conn: &impl Executor) -> Result<Vec<JMap<String, JValue>>, Error> {
+        let sql = self.sql()?;
+        debug!("Get objects sql = {}", &sql);
+        self.run(conn, &sql, |stmt| {
+            let keys = json::object_keys(&stmt.column_names()?);
+            let mut result = Vec::new();
+            while let Some(row) = Self::next_row(stmt)? {
+                result.push(self.row2obj(&keys, &row)?);
+            }
+            Ok(result)
+        })
+    }
+
+    /// Execute and return first row as JSON object keyed by column names
+    pub fn get_object_row(&self, conn: &impl Executor) -> Result<JMap<String, JValue>, Error> {
+        let sql = self.sql()?;
+        debug!("Get object row sql = {}", &sql);
+        self.run(conn, &sql, |stmt| {
+            let keys = json::object_keys(&stmt.column_names()?);
+            let first_row = if let Some(row) = Self::next_row(stmt)? {
+                self.row2obj(&keys, &row)?
+            } else {
+                JMap::new()
+            };
+            Ok(first_row)
+        })
+    }
+
+    /// Execute and convert all rows to typed values
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+    /// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+    /// # let conn = pool.get()?;
+    /// # conn.execute("CREATE TABLE books (title TEXT, price INTEGER); INSERT INTO books VALUES ('Dune', 120);")?;
+    /// let books = Sqlite3Builder::select_from("books")
+    ///     .field("title")
+    ///     .field("price")
+    ///     .get_as::<(String, i64)>(&conn)?;
+    ///
+    /// assert_eq!(books, vec![("Dune".to_string(), 120)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_as<T: FromRow>(&self, conn: &impl Executor) -> Result<Vec<T>, Error> {
+        let sql = self.sql()?;
+        debug!("Get typed rows sql = {}", &sql);
+        self.run(conn, &sql, |stmt| {
+            let columns = stmt.column_names()?;
+            let mut result = Vec::new();
+            while let Some(row) = Self::next_row(stmt)? {
+                result.push(T::from_row(&Row::new(&columns, &row))?);
+            }
+            Ok(result)
+        })
+    }
+
+    /// Execute and convert first row to typed value
+    pub fn get_one_as<T: FromRow>(&self, conn: &impl Executor) -> Result<T, Error> {
+        let sql = self.sql()?;
+        debug!("Get typed row sql = {}", &sql);
+        self.run(conn, &sql, |stmt| {
+            let columns = stmt.column_names()?;
+            match Self::next_row(stmt)? {
+                Some(row) => T::from_row(&Row::new(&columns, &row)),
+                None => Err(Error::NoRows),
+            }
+        })
+    }
+
+    /// Execute and deserialize all rows keyed by column names.
+    /// Repeated column names are renamed as in `get_objects`.
+    /// Values are converted as in `get`, so set `BlobEncoding::Array` for `Vec<u8>` fields.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use serde::Deserialize;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Book {
+    ///     title: String,
+    ///     price: i64,
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+    /// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+    /// # let conn = pool.get()?;
+    /// # conn.execute("CREATE TABLE books (title TEXT, price INTEGER); INSERT INTO books VALUES ('Dune', 120);")?;
+    /// let books = Sqlite3Builder::select_from("books")
+    ///     .field("price")
+    ///     .field("title")
+    ///     .get_typed::<Book>(&conn)?;
+    ///
+    /// assert_eq!(books[0].title, "Dune");
+    /// assert_eq!(books[0].price, 120);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_typed<T: DeserializeOwned>(&self, conn: &impl Executor) -> Result<Vec<T>, Error> {
+        let sql = self.sql()?;
+        debug!("Get deserialized rows sql = {}", &sql);
+        self.run(conn, &sql, |stmt| {
+            let keys = json::object_keys(&stmt.column_names()?);
+            let mut result = Vec::new();
+            while let Some(row) = Self::next_row(stmt)? {
+                result.push(de::from_row(&keys, self.row2j(&row)?)?);
+            }
+            Ok(result)
+        })
     }
 
     /// Execute and return first value
-    pub fn get_value(&self, conn: &ConnPooled) -> Result<JValue, Box<dyn Error>> {
-        let sql = self.builder.sql()?;
+    pub fn get_value(&self, conn: &impl Executor) -> Result<JValue, Error> {
+        let sql = self.sql()?;
         debug!("Get value sql = {}", &sql);
-        let mut cursor = conn.prepare(sql)?.cursor();
-        let first_value = if let Some(row) = cursor.next()? {
-            Self::s2j(&row[0])?
-        } else {
-            return Err("No any value".into());
-        };
-        Ok(first_value)
+        self.run(conn, &sql, |stmt| match Self::next_row(stmt)? {
+            Some(row) => self.s2j(&row[0]),
+            None => Err(Error::NoRows),
+        })
     }
 
     /// Execute and return first integer value
-    pub fn get_int(&self, conn: &ConnPooled) -> Result<i64, Box<dyn Error>> {
-        Ok(self.get_value(&conn)?.as_i64().unwrap())
+    pub fn get_int(&self, conn: &impl Executor) -> Result<i64, Error> {
+        self.get_i64(conn)
     }
 
     /// Execute and return first string value
-    pub fn get_str(&self, conn: &ConnPooled) -> Result<String, Box<dyn Error>> {
-        Ok(self.get_value(&conn)?.as_str().unwrap().to_string())
+    pub fn get_str(&self, conn: &impl Executor) -> Result<String, Error> {
+        self.get_string(conn)
+    }
+
+    /// Execute and convert first value of first row.
+    /// Returns `Error::NoRows` if there is no row.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::Sqlite3Builder;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+    /// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+    /// # let conn = pool.get()?;
+    /// # conn.execute("CREATE TABLE books (title TEXT, price INTEGER); INSERT INTO books VALUES ('Dune', NULL);")?;
+    /// let price = Sqlite3Builder::select_from("books")
+    ///     .field("price")
+    ///     .get_scalar::<Option<u32>>(&conn)?;
+    ///
+    /// assert_eq!(price, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_scalar<T: FromValue>(&self, conn: &impl Executor) -> Result<T, Error> {
+        let sql = self.sql()?;
+        debug!("Get scalar sql = {}", &sql);
+        let (name, row) = self.run(conn, &sql, |stmt| {
+            let name = stmt.column_names()?.into_iter().next().unwrap_or_default();
+            Ok((name, Self::next_row(stmt)?.ok_or(Error::NoRows)?))
+        })?;
+        let value = row
+            .first()
+            .ok_or_else(|| Error::conversion("no such column").at_column(0, &name))?;
+        let result = if self.coerce {
+            T::from_value_coerced(value)
+        } else {
+            T::from_value(value)
+        };
+        result.map_err(|err| err.at_column(0, &name))
+    }
+
+    /// Execute and return first value or None if it is NULL or there is no row
+    fn get_opt_scalar<T: FromValue>(&self, conn: &impl Executor) -> Result<Option<T>, Error> {
+        match self.get_scalar::<Option<T>>(conn) {
+            Err(Error::NoRows) => Ok(None),
+            res => res,
+        }
+    }
+
+    /// Execute and return first value as integer
+    pub fn get_i64(&self, conn: &impl Executor) -> Result<i64, Error> {
+        self.get_scalar(conn)
+    }
+
+    /// Execute and return first value as float
+    pub fn get_f64(&self, conn: &impl Executor) -> Result<f64, Error> {
+        self.get_scalar(conn)
+    }
+
+    /// Execute and return first value as string
+    pub fn get_string(&self, conn: &impl Executor) -> Result<String, Error> {
+        self.get_scalar(conn)
+    }
+
+    /// Execute and return first value as boolean
+    pub fn get_bool(&self, conn: &impl Executor) -> Result<bool, Error> {
+        self.get_scalar(conn)
+    }
+
+    /// Execute and return first value as bytes
+    pub fn get_bytes(&self, conn: &impl Executor) -> Result<Vec<u8>, Error> {
+        self.get_scalar(conn)
+    }
+
+    /// Execute and return first value as integer, None if NULL or no row
+    pub fn get_opt_i64(&self, conn: &impl Executor) -> Result<Option<i64>, Error> {
+        self.get_opt_scalar(conn)
+    }
+
+    /// Execute and return first value as float, None if NULL or no row
+    pub fn get_opt_f64(&self, conn: &impl Executor) -> Result<Option<f64>, Error> {
+        self.get_opt_scalar(conn)
+    }
+
+    /// Execute and return first value as string, None if NULL or no row
+    pub fn get_opt_string(&self, conn: &impl Executor) -> Result<Option<String>, Error> {
+        self.get_opt_scalar(conn)
+    }
+
+    /// Execute and return first value as boolean, None if NULL or no row
+    pub fn get_opt_bool(&self, conn: &impl Executor) -> Result<Option<bool>, Error> {
+        self.get_opt_scalar(conn)
+    }
+
+    /// Execute and return first value as bytes, None if NULL or no row
+    pub fn get_opt_bytes(&self, conn: &impl Executor) -> Result<Option<Vec<u8>>, Error> {
+        self.get_opt_scalar(conn)
     }
 
     /// Get cursor for request
-    pub fn get_cursor<'a>(&'a self, conn: &'a ConnPooled) -> Result<Cursor<'a>, Box<dyn Error>> {
-        let sql = self.builder.sql()?;
+    pub fn get_cursor<'a>(&'a self, conn: &'a impl Executor) -> Result<Cursor<'a>, Error> {
+        let sql = self.sql()?;
         debug!("Get cursor sql = {}", &sql);
-        let cursor = conn.prepare(sql)?.cursor();
+        let cursor = self.prepare(conn, &sql)?.cursor();
         Ok(cursor)
     }
 }
 
+/// Check that SQL has no statements after the first one, which prepare would ignore
+fn check_single_statement(sql: &str) -> Result<(), Error> {
+    let mut chars = sql.char_indices().peekable();
+    let mut end = None;
+    while let Some((pos, c)) = chars.next() {
+        let close = match c {
+            '-' if chars.peek().map(|&(_, next)| next) == Some('-') => Some('\n'),
+            '/' if chars.peek().map(|&(_, next)| next) == Some('*') => {
+                chars.next();
+                let mut star = false;
+                for (_, c) in chars.by_ref() {
+                    if star && c == '/' {
+                        break;
+                    }
+                    star = c == '*';
+                }
+                None
+            }
+            ';' => {
+                end = end.or(Some(pos));
+                None
+            }
+            c if end.is_some() && !c.is_whitespace() => {
+                return Err(Error::Build(format!(
+                    "Multiple statements in one command: {}",
+                    sql[pos..].trim()
+                )));
+            }
+            '\'' | '"' | '`' => Some(c),
+            '[' => Some(']'),
+            _ => None,
+        };
+        if let Some(close) = close {
+            for (_, c) in chars.by_ref() {
+                if c == close {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that linked SQLite library supports feature
+fn require_sqlite(version: usize, feature: &str) -> Result<(), Error> {
+    let linked = sqlite3::version();
+    if linked >= version {
+        return Ok(());
+    }
+    let release = |ver: usize| format!("{}.{}.{}", ver / 1_000_000, ver / 1000 % 1000, ver % 1000);
+    Err(Error::Build(format!(
+        "{} requires SQLite {} or later, linked {}",
+        feature,
+        release(version),
+        release(linked)
+    )))
+}
+
 /// Escape string for SQL.
 ///
 /// ```
@@ -2225,6 +3562,7 @@ pub fn quote(src: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::error::Error;
 
     #[test]
     fn test_esc() -> Result<(), Box<dyn Error>> {
@@ -2722,4 +4060,547 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_get_float_and_blob() -> Result<(), Box<dyn Error>> {
+        let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+        let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+        let conn = pool.get()?;
+
+        let row = Sqlite3Builder::select_values(&["1.5", "X'01FF'", "1e999"])
+            .blob_encoding(BlobEncoding::Hex)
+            .get_row(&conn)?;
+
+        assert_eq!(row, vec![1.5.into(), "01ff".into(), JValue::Null]);
+
+        let res = Sqlite3Builder::select_values(&["-1e999"])
+            .non_finite(NonFinite::Error)
+            .get(&conn);
+
+        assert!(res.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bind_params() -> Result<(), Box<dyn Error>> {
+        let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+        let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+        let conn = pool.get()?;
+
+        conn.execute("CREATE TABLE books (title TEXT, price INTEGER, cover BLOB);")?;
+
+        let mut db = Sqlite3Builder::insert_into("books");
+        let title = db.param("Don't Panic");
+        let price = db.param(42);
+        let cover = db.param(vec![0u8, 1, 2]);
+        db.fields(&["title", "price", "cover"])
+            .values(&[title, price, cover])
+            .exec(&conn)?;
+
+        Sqlite3Builder::insert_into("books")
+            .fields(&["title", "price", "cover"])
+            .values(&["?", "?", "?"])
+            .bind("Dune")
+            .bind(None::<i64>)
+            .bind(None::<Vec<u8>>)
+            .exec(&conn)?;
+
+        let mut db = Sqlite3Builder::select_from("books");
+        let price = db.param(40);
+        let rows = db
+            .fields(&["title", "price", "cover"])
+            .and_where_gt("price", price)
+            .blob_encoding(BlobEncoding::Array)
+            .get(&conn)?;
+
+        assert_eq!(
+            rows,
+            vec![vec![
+                "Don't Panic".into(),
+                42.into(),
+                serde_json::json!([0, 1, 2])
+            ]]
+        );
+
+        let mut db = Sqlite3Builder::select_from("books");
+        let title = db.param("Dune");
+        let price = db
+            .field("price")
+            .and_where_eq("title", title)
+            .get_value(&conn)?;
+
+        assert_eq!(price, JValue::Null);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_single_statement() -> Result<(), Box<dyn Error>> {
+        let conn = sqlite3::open(":memory:")?;
+        conn.execute("CREATE TABLE books (title TEXT, price INTEGER);")?;
+
+        Sqlite3Builder::insert_into("books")
+            .field("title")
+            .values(&["'a;b' /* c; d */"])
+            .exec(&conn)?;
+
+        let res = Sqlite3Builder::delete_from("books")
+            .and_where("1; DROP TABLE books")
+            .exec(&conn);
+        assert!(
+            matches!(res, Err(super::Error::Build(msg)) if msg == "Multiple statements in one command: DROP TABLE books;")
+        );
+        assert_eq!(
+            Sqlite3Builder::select_from("books")
+                .field("title")
+                .get_value(&conn)?,
+            JValue::from("a;b")
+        );
+
+        let mut db = Sqlite3Builder::select_from("books");
+        let title = db.param("a;b");
+        db.field("title").and_where_eq("title", title);
+        assert!(matches!(db.subquery(), Err(super::Error::Build(_))));
+
+        let mut db = Sqlite3Builder::select_values(&["?1"]);
+        db.bind(5);
+        assert!(matches!(db.query_values(), Err(super::Error::Build(_))));
+
+        let db = Sqlite3Builder::select_from("books; DROP TABLE books");
+        assert!(matches!(db.get_cursor(&conn), Err(super::Error::Build(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_as() -> Result<(), Box<dyn Error>> {
+        let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+        let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+        let conn = pool.get()?;
+
+        conn.execute(
+            "CREATE TABLE books (title TEXT, price INTEGER); \
+             INSERT INTO books VALUES ('Dune', 120), ('Emma', NULL);",
+        )?;
+
+        struct Book {
+            title: String,
+            price: Option<i64>,
+        }
+
+        impl FromRow for Book {
+            fn from_row(row: &Row) -> Result<Self, crate::Error> {
+                Ok(Self {
+                    title: row.get_named("title")?,
+                    price: row.get_named("price")?,
+                })
+            }
+        }
+
+        let books = Sqlite3Builder::select_from("books")
+            .fields(&["title", "price"])
+            .order_asc("title")
+            .get_as::<Book>(&conn)?;
+
+        assert_eq!(books.len(), 2);
+        assert_eq!(books[0].title, "Dune");
+        assert_eq!(books[0].price, Some(120));
+        assert_eq!(books[1].price, None);
+
+        let err = Sqlite3Builder::select_from("books")
+            .fields(&["title", "price"])
+            .and_where_is_null("price")
+            .get_one_as::<(String, i64)>(&conn)
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            err.to_string(),
+            "Column 1 (price): Expected Integer, found Null"
+        );
+
+        let res = Sqlite3Builder::select_from("books")
+            .and_where("price > 1000")
+            .get_one_as::<(String,)>(&conn);
+
+        assert!(res.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_scalars() -> Result<(), Box<dyn Error>> {
+        let conn = sqlite3::open(":memory:")?;
+
+        conn.execute(
+            "CREATE TABLE books (title TEXT, price INTEGER, rating TEXT); \
+             INSERT INTO books VALUES ('Dune', NULL, '4.5');",
+        )?;
+
+        let mut db = Sqlite3Builder::select_from("books");
+        db.field("price");
+
+        assert_eq!(db.get_opt_i64(&conn)?, None);
+
+        let err = db.get_i64(&conn).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Column 0 (price): Expected Integer, found Null"
+        );
+
+        let err = db.get_int(&conn).err().unwrap();
+        assert!(matches!(err, crate::Error::Conversion { .. }));
+
+        db.and_where("price > 0");
+        assert!(matches!(db.get_i64(&conn), Err(crate::Error::NoRows)));
+        assert_eq!(db.get_opt_string(&conn)?, None);
+
+        let mut db = Sqlite3Builder::select_from("books");
+        db.field("rating");
+
+        assert!(db.get_f64(&conn).is_err());
+        assert_eq!(db.get_string(&conn)?, "4.5");
+        assert_eq!(db.get_bytes(&conn).ok(), None);
+
+        db.coerce_types(true);
+        assert_eq!(db.get_f64(&conn)?, 4.5);
+        assert_eq!(db.get_opt_f64(&conn)?, Some(4.5));
+        assert_eq!(db.get_bytes(&conn)?, b"4.5");
+        assert!(db.get_i64(&conn).is_err());
+
+        assert!(Sqlite3Builder::select_values(&["1"]).get_bool(&conn)?);
+        assert_eq!(
+            Sqlite3Builder::select_values(&["0"]).get_opt_bool(&conn)?,
+            Some(false)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exec_result() -> Result<(), Box<dyn Error>> {
+        let conn = sqlite3::open(":memory:")?;
+
+        conn.execute("CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT);")?;
+
+        let res = Sqlite3Builder::insert_into("books")
+            .field("title")
+            .values(&["'Dune'"])
+            .values(&["'Emma'"])
+            .exec(&conn)?;
+
+        assert_eq!(res.changes(), 2);
+        assert_eq!(res.last_insert_rowid(), 2);
+
+        let id = Sqlite3Builder::insert_into("books")
+            .field("title")
+            .values(&["'Ulysses'"])
+            .insert_returning_id(&conn)?;
+
+        assert_eq!(id, 3);
+
+        let res = Sqlite3Builder::delete_from("books")
+            .and_where("id < 3")
+            .exec(&conn)?;
+
+        assert_eq!(res.changes(), 2);
+        assert_eq!(res.last_insert_rowid(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_returning() -> Result<(), Box<dyn Error>> {
+        let conn = sqlite3::open(":memory:")?;
+
+        conn.execute(
+            "CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT, price INTEGER); \
+             INSERT INTO books (title, price) VALUES ('Dune', 120), ('Emma', 80);",
+        )?;
+
+        let mut db = Sqlite3Builder::update_table("books");
+        db.set("price", "price + 10")
+            .and_where("price > 100")
+            .returning(&["title", "price"]);
+
+        assert_eq!(
+            "UPDATE books SET price = price + 10 WHERE price > 100 RETURNING title, price;",
+            &db.sql()?
+        );
+        assert_eq!(db.get_row(&conn)?, vec![JValue::from("Dune"), 130.into()]);
+
+        let mut db = Sqlite3Builder::delete_from("books");
+        db.and_where("price < 100").returning(&["id"]);
+
+        assert_eq!(
+            "DELETE FROM books WHERE price < 100 RETURNING id;",
+            &db.sql()?
+        );
+        assert_eq!(db.get(&conn)?, vec![vec![JValue::from(2)]]);
+
+        let err = Sqlite3Builder::select_from("books")
+            .returning(&["id"])
+            .sql()
+            .unwrap_err();
+
+        assert!(matches!(err, crate::Error::Build(_)));
+
+        assert!(require_sqlite(3_035_000, "RETURNING").is_ok());
+        assert!(require_sqlite(99_000_000, "RETURNING")
+            .unwrap_err()
+            .to_string()
+            .starts_with("RETURNING requires SQLite 99.0.0 or later, linked 3."));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_upsert() -> Result<(), Box<dyn Error>> {
+        let conn = sqlite3::open(":memory:")?;
+
+        conn.execute(
+            "CREATE TABLE books (isbn TEXT PRIMARY KEY, title TEXT, price INTEGER); \
+             INSERT INTO books VALUES ('1', 'Dune', 120);",
+        )?;
+
+        let mut db = Sqlite3Builder::insert_into("books");
+        db.fields(&["isbn", "title", "price"])
+            .values(&["'1', 'Dune Messiah', 90"])
+            .values(&["'2', 'Emma', 80"])
+            .on_conflict(&["isbn"])
+            .do_update_all()
+            .do_update_where("excluded.price < books.price");
+
+        assert_eq!(db.exec(&conn)?.changes(), 2);
+
+        let books = Sqlite3Builder::select_from("books")
+            .fields(&["title", "price"])
+            .order_asc("isbn")
+            .get_as::<(String, i64)>(&conn)?;
+
+        assert_eq!(
+            books,
+            vec![("Dune Messiah".to_string(), 90), ("Emma".to_string(), 80)]
+        );
+
+        let res = Sqlite3Builder::insert_into("books")
+            .field("isbn, title")
+            .values(&["'2', 'Persuasion'"])
+            .do_nothing()
+            .exec(&conn)?;
+
+        assert_eq!(res.changes(), 0);
+
+        let mut db = Sqlite3Builder::insert_into("books");
+        db.field("isbn, title, price")
+            .select("SELECT isbn, title, 0 FROM books")
+            .on_conflict(&["isbn"])
+            .do_update_set("price", "excluded.price");
+
+        assert_eq!(
+            db.sql()?,
+            "INSERT INTO books (isbn, title, price) SELECT isbn, title, 0 FROM books WHERE true \
+             ON CONFLICT (isbn) DO UPDATE SET price = excluded.price;"
+        );
+        assert_eq!(db.exec(&conn)?.changes(), 2);
+
+        let err = Sqlite3Builder::update_table("books")
+            .set("price", "0")
+            .do_nothing()
+            .sql()
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "ON CONFLICT requires INSERT");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflict_algorithm() -> Result<(), Box<dyn Error>> {
+        let conn = sqlite3::open(":memory:")?;
+
+        conn.execute(
+            "CREATE TABLE books (isbn TEXT PRIMARY KEY, title TEXT); \
+             INSERT INTO books VALUES ('1', 'Dune');",
+        )?;
+
+        let res = Sqlite3Builder::insert_into("books")
+            .fields(&["isbn", "title"])
+            .values(&["'1', 'Emma'"])
+            .values(&["'2', 'Emma'"])
+            .or_ignore()
+            .exec(&conn)?;
+
+        assert_eq!(res.changes(), 1);
+
+        Sqlite3Builder::replace_into("books")
+            .fields(&["isbn", "title"])
+            .values(&["'1', 'Dune Messiah'"])
+            .exec(&conn)?;
+
+        let title = Sqlite3Builder::select_from("books")
+            .field("title")
+            .and_where_eq("isbn", "'1'")
+            .get_string(&conn)?;
+
+        assert_eq!(title, "Dune Messiah");
+
+        let err = Sqlite3Builder::update_table("books")
+            .set("isbn", "'2'")
+            .and_where_eq("isbn", "'1'")
+            .or_fail()
+            .exec(&conn)
+            .unwrap_err();
+
+        assert!(err.is_constraint());
+
+        let sql = Sqlite3Builder::update_table("books")
+            .set("title", "'Emma'")
+            .or_rollback()
+            .sql()?;
+
+        assert_eq!(sql, "UPDATE OR ROLLBACK books SET title = 'Emma';");
+
+        let err = Sqlite3Builder::delete_from("books")
+            .or_abort()
+            .sql()
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "OR ABORT requires INSERT or UPDATE");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_with() -> Result<(), Box<dyn Error>> {
+        let conn = sqlite3::open(":memory:")?;
+
+        conn.execute(
+            "CREATE TABLE books (title TEXT, price INTEGER); \
+             CREATE TABLE archive (title TEXT); \
+             INSERT INTO books VALUES ('Dune', 120), ('Emma', 80), ('Ulysses', 90);",
+        )?;
+
+        let mut cheap = Sqlite3Builder::select_from("books");
+        let max_price = cheap.param(100);
+        cheap.field("title").and_where_lt("price", &max_price);
+
+        let mut db = Sqlite3Builder::insert_into("archive");
+        db.with_not_materialized("cheap", &cheap)
+            .field("title")
+            .select(
+                Sqlite3Builder::select_from("cheap")
+                    .field("title")
+                    .query()?,
+            );
+
+        assert_eq!(
+            db.sql()?,
+            "WITH cheap AS NOT MATERIALIZED (SELECT title FROM books WHERE price < ?1) \
+             INSERT INTO archive (title) SELECT title FROM cheap;"
+        );
+        assert_eq!(db.exec(&conn)?.changes(), 2);
+
+        let cheap_titles = Sqlite3Builder::select_from("books")
+            .with("cheap", &cheap)
+            .field("COUNT(*)")
+            .and_where("title IN (SELECT title FROM cheap)")
+            .get_i64(&conn)?;
+
+        assert_eq!(cheap_titles, 2);
+
+        let mut db = Sqlite3Builder::select_from("cheap");
+        let other = db.param(1);
+        db.with("cheap", &cheap).and_where_gt("price", &other);
+
+        assert_eq!(
+            db.sql().unwrap_err().to_string(),
+            "Common table expression cheap: Subquery parameters clash with builder parameters"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_functions() -> Result<(), Box<dyn Error>> {
+        let conn = sqlite3::open(":memory:")?;
+
+        conn.execute(
+            "CREATE TABLE books (title TEXT, author TEXT, price INTEGER); \
+             INSERT INTO books VALUES ('Dune', 'Herbert', 120), ('Children of Dune', 'Herbert', 90), \
+             ('Emma', 'Austen', 80), ('Persuasion', 'Austen', 70);",
+        )?;
+
+        let mut by_price = Window::new();
+        by_price.partition_by("author").order_desc("price");
+
+        let rows = Sqlite3Builder::select_from("books")
+            .field("title")
+            .field(rank().over_named("w"))
+            .field(lead("price", 1).over_named("w"))
+            .field(aggregate("COUNT(*)").filter("price >= 80").over(
+                Window::based_on("w").rows_between(
+                    FrameBound::UnboundedPreceding,
+                    FrameBound::UnboundedFollowing,
+                ),
+            ))
+            .window("w", &by_price)
+            .and_where("price > 10")
+            .order_asc("title")
+            .limit(3)
+            .get_as::<(String, i64, Option<i64>, i64)>(&conn)?;
+
+        assert_eq!(
+            rows,
+            vec![
+                ("Children of Dune".to_string(), 2, None, 2),
+                ("Dune".to_string(), 1, Some(90), 2),
+                ("Emma".to_string(), 1, Some(70), 1),
+            ]
+        );
+
+        let mut db = Sqlite3Builder::select_from("books");
+        db.field(ntile(2).over_named("w"))
+            .window("w", Window::new().order_asc("price"))
+            .union_all("SELECT 0");
+
+        assert_eq!(
+            db.query()?,
+            "SELECT ntile(2) OVER w FROM books WINDOW w AS (ORDER BY price) UNION ALL SELECT 0"
+        );
+
+        assert_eq!(
+            Sqlite3Builder::select_values(&["1"])
+                .union_all("SELECT 2")
+                .limit(1)
+                .query_values()?,
+            "SELECT 1 UNION ALL SELECT 2 LIMIT 1"
+        );
+
+        let err = Sqlite3Builder::delete_from("books")
+            .window("w", &by_price)
+            .sql()
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "WINDOW requires SELECT");
+
+        let err = Sqlite3Builder::update_table("books")
+            .set("price", 1)
+            .order_asc("price")
+            .limit(1)
+            .sql()
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "UNION, ORDER BY, LIMIT and OFFSET require SELECT"
+        );
+        assert!(Sqlite3Builder::delete_from("books")
+            .union("SELECT 1")
+            .sql()
+            .is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,856 @@
+//! Versioned schema migrations.
+
+use crate::ddl::{execute, Column, ToSql};
+use crate::error::Error;
+use crate::executor::Executor;
+use crate::row::{FromRow, Row};
+use crate::transaction::{Behavior, Transaction};
+use crate::Sqlite3Builder;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Default name of bookkeeping table
+pub const DEFAULT_MIGRATIONS_TABLE: &str = "schema_migrations";
+
+/// Rust step of migration
+type Action = Arc<dyn Fn(&Transaction) -> Result<(), Error> + Send + Sync>;
+
+/// Single schema change: SQL scripts, builder statements and optional Rust closure,
+/// with optional down steps reverting it.
+/// Checksum covers SQL text of up steps only, closure changes are not detected.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::{Migration, Sqlite3Builder};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mut migration = Migration::new(2, "add_isbn");
+/// migration
+///     .sql("ALTER TABLE books ADD COLUMN isbn TEXT;")
+///     .statement(Sqlite3Builder::update_table("books").set("isbn", "''"))
+///     .run(|tx| {
+///         let books = Sqlite3Builder::select_from("books").field("COUNT(*)").get_i64(tx)?;
+///         println!("{} books got ISBN", books);
+///         Ok(())
+///     });
+///
+/// assert_eq!("ALTER TABLE books ADD COLUMN isbn TEXT;\nUPDATE books SET isbn = '';", &migration.script()?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Migration {
+    version: i64,
+    name: String,
+    statements: Vec<Result<String, String>>,
+    action: Option<Action>,
+    down: Vec<Result<String, String>>,
+    down_action: Option<Action>,
+}
+
+impl Migration {
+    /// Create empty migration with version and name
+    pub fn new<S: ToString>(version: i64, name: S) -> Self {
+        Self {
+            version,
+            name: name.to_string(),
+            statements: Vec::new(),
+            action: None,
+            down: Vec::new(),
+            down_action: None,
+        }
+    }
+
+    /// Migration version
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Migration name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add SQL script, it may contain several statements
+    pub fn sql<S: ToString>(&mut self, sql: S) -> &mut Self {
+        self.statements.push(Ok(sql.to_string()));
+        self
+    }
+
+    /// Add builder statement, it can't have bound parameters
+    pub fn statement(&mut self, query: &impl ToSql) -> &mut Self {
+        self.statements
+            .push(query.to_sql().map_err(|err| err.to_string()));
+        self
+    }
+
+    /// Set closure run after SQL in the same transaction
+    pub fn run<F>(&mut self, action: F) -> &mut Self
+    where
+        F: Fn(&Transaction) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        self.action = Some(Arc::new(action));
+        self
+    }
+
+    /// Add SQL script reverting migration
+    pub fn down_sql<S: ToString>(&mut self, sql: S) -> &mut Self {
+        self.down.push(Ok(sql.to_string()));
+        self
+    }
+
+    /// Add builder statement reverting migration
+    pub fn down_statement(&mut self, query: &impl ToSql) -> &mut Self {
+        self.down
+            .push(query.to_sql().map_err(|err| err.to_string()));
+        self
+    }
+
+    /// Set closure run after down SQL in the same transaction
+    pub fn down_run<F>(&mut self, action: F) -> &mut Self
+    where
+        F: Fn(&Transaction) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        self.down_action = Some(Arc::new(action));
+        self
+    }
+
+    /// True if migration has down steps
+    pub fn is_reversible(&self) -> bool {
+        !self.down.is_empty() || self.down_action.is_some()
+    }
+
+    /// Whole SQL text of migration
+    pub fn script(&self) -> Result<String, Error> {
+        self.join(&self.statements)
+    }
+
+    /// Whole SQL text reverting migration
+    pub fn down_script(&self) -> Result<String, Error> {
+        self.join(&self.down)
+    }
+
+    /// Join SQL texts of steps
+    fn join(&self, statements: &[Result<String, String>]) -> Result<String, Error> {
+        let statements = statements
+            .iter()
+            .map(|stmt| {
+                stmt.clone()
+                    .map_err(|err| Error::Migration(format!("Migration {}: {}", self.label(), err)))
+            })
+            .collect::<Result<Vec<String>, Error>>()?;
+        Ok(statements.join("\n"))
+    }
+
+    /// Checksum of SQL text
+    pub fn checksum(&self) -> Result<String, Error> {
+        Ok(checksum(&self.script()?))
+    }
+
+    /// Version and name for messages
+    fn label(&self) -> String {
+        format!("{} ({})", self.version, self.name)
+    }
+
+    /// Apply migration in transaction and record it.
+    /// Returns false if other connection applied it already.
+    fn apply(&self, conn: &impl Executor, table: &str) -> Result<bool, Error> {
+        let script = self.script()?;
+        let tx = Transaction::with_behavior(conn, Behavior::Immediate)?;
+
+        let mut db = Sqlite3Builder::select_from(table);
+        let version = db.param(self.version);
+        let applied = db
+            .field("COUNT(*)")
+            .and_where_eq("version", &version)
+            .get_i64(&tx)?;
+        if applied > 0 {
+            return Ok(false);
+        }
+
+        info!("Apply migration {}", self.label());
+        if !script.trim().is_empty() {
+            execute(&tx, &script)?;
+        }
+        if let Some(action) = &self.action {
+            action(&tx)?;
+        }
+
+        let mut db = Sqlite3Builder::insert_into(table);
+        let version = db.param(self.version);
+        let name = db.param(&self.name);
+        let checksum = db.param(checksum(&script));
+        db.fields(&["version", "name", "checksum"])
+            .values(&[version, name, checksum])
+            .exec(&tx)?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Run down steps in transaction and forget migration.
+    /// Returns false if other connection reverted it already.
+    fn revert(&self, conn: &impl Executor, table: &str) -> Result<bool, Error> {
+        let script = self.down_script()?;
+        let tx = Transaction::with_behavior(conn, Behavior::Immediate)?;
+
+        let mut db = Sqlite3Builder::delete_from(table);
+        let version = db.param(self.version);
+        db.and_where_eq("version", &version);
+        if db.exec(&tx)?.changes() == 0 {
+            return Ok(false);
+        }
+
+        info!("Revert migration {}", self.label());
+        if !script.trim().is_empty() {
+            execute(&tx, &script)?;
+        }
+        if let Some(action) = &self.down_action {
+            action(&tx)?;
+        }
+
+        tx.commit()?;
+        Ok(true)
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("name", &self.name)
+            .field("statements", &self.statements)
+            .field("action", &self.action.is_some())
+            .field("down", &self.down)
+            .field("down_action", &self.down_action.is_some())
+            .finish()
+    }
+}
+
+/// State of migration in database
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigrationState {
+    /// Applied with the same checksum
+    Applied,
+    /// Not applied yet
+    Pending,
+    /// Applied, but its SQL changed since
+    Modified,
+    /// Applied, but not known to runner
+    Unknown,
+}
+
+/// Status line of migration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationStatus {
+    version: i64,
+    name: String,
+    state: MigrationState,
+    applied_at: Option<String>,
+}
+
+impl MigrationStatus {
+    /// Migration version
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Migration name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Migration state
+    pub fn state(&self) -> MigrationState {
+        self.state
+    }
+
+    /// Time of applying in UTC, `YYYY-MM-DD HH:MM:SS`
+    pub fn applied_at(&self) -> Option<&str> {
+        self.applied_at.as_deref()
+    }
+}
+
+/// Migration record of bookkeeping table
+struct Applied {
+    version: i64,
+    name: String,
+    checksum: String,
+    applied_at: String,
+}
+
+impl FromRow for Applied {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        Ok(Self {
+            version: row.get(0)?,
+            name: row.get(1)?,
+            checksum: row.get(2)?,
+            applied_at: row.get(3)?,
+        })
+    }
+}
+
+/// Runner of ordered migrations.
+/// Applied versions and checksums are recorded in bookkeeping table,
+/// every migration runs in its own transaction.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::{Migration, Migrator, Sqlite3Builder};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+/// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+/// let conn = pool.get()?;
+///
+/// let mut migrator = Migrator::new();
+/// migrator
+///     .add(Migration::new(1, "create_books").sql("CREATE TABLE books (title TEXT);"))
+///     .add(Migration::new(2, "add_price").sql("ALTER TABLE books ADD COLUMN price INTEGER;"));
+///
+/// assert_eq!(vec![1, 2], migrator.migrate(&conn)?);
+/// assert!(migrator.migrate(&conn)?.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Migrator {
+    table: String,
+    migrations: Vec<Migration>,
+}
+
+impl Default for Migrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Migrator {
+    /// Create runner without migrations
+    pub fn new() -> Self {
+        Self {
+            table: DEFAULT_MIGRATIONS_TABLE.to_string(),
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Set name of bookkeeping table
+    pub fn table<S: ToString>(&mut self, name: S) -> &mut Self {
+        self.table = name.to_string();
+        self
+    }
+
+    /// Add migration, versions must increase
+    pub fn add(&mut self, migration: &Migration) -> &mut Self {
+        self.migrations.push(migration.clone());
+        self
+    }
+
+    /// Add migrations from `NNNN_name.sql` files of directory in order of versions.
+    /// Down steps are read from optional `NNNN_name.down.sql` files.
+    /// Other files are skipped.
+    pub fn load_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<&mut Self, Error> {
+        let dir = dir.as_ref();
+        let io_error = |err: std::io::Error| {
+            Error::Migration(format!("Cannot read {}: {}", dir.display(), err))
+        };
+        let mut migrations = Vec::new();
+        let mut downs = Vec::new();
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            let file_name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) if name.ends_with(".sql") && path.is_file() => name,
+                _ => continue,
+            };
+            let (version, name, down) = parse_file_name(file_name)?;
+            let sql = fs::read_to_string(&path).map_err(|err| {
+                Error::Migration(format!("Cannot read {}: {}", path.display(), err))
+            })?;
+            if down {
+                downs.push((version, name.to_string(), sql));
+            } else {
+                let mut migration = Migration::new(version, name);
+                migration.sql(sql);
+                migrations.push(migration);
+            }
+        }
+        for (version, name, sql) in downs {
+            let migration = migrations
+                .iter_mut()
+                .find(|m| m.version == version && m.name == name)
+                .ok_or_else(|| {
+                    Error::Migration(format!(
+                        "Down migration {}_{} has no up migration",
+                        version, name
+                    ))
+                })?;
+            migration.down_sql(sql);
+        }
+        migrations.sort_by_key(Migration::version);
+        self.migrations.extend(migrations);
+        Ok(self)
+    }
+
+    /// Added migrations
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Apply pending migrations, returns their versions.
+    /// Fails before applying anything if checksum of applied migration changed.
+    /// Must not be executed inside transaction.
+    pub fn migrate(&self, conn: &impl Executor) -> Result<Vec<i64>, Error> {
+        self.migrate_to(conn, i64::MAX)
+    }
+
+    /// Apply pending migrations up to version and revert applied ones above it,
+    /// newest first. Returns versions in order of applying or reverting.
+    /// Fails before changing anything if checksum of applied migration changed
+    /// or migration to revert is unknown or has no down steps.
+    /// Must not be executed inside transaction.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::{Migration, MigrationState, Migrator};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let conn = sqlite3::open(":memory:")?;
+    ///
+    /// let mut migrator = Migrator::new();
+    /// migrator
+    ///     .add(
+    ///         Migration::new(1, "create_books")
+    ///             .sql("CREATE TABLE books (title TEXT);")
+    ///             .down_sql("DROP TABLE books;"),
+    ///     )
+    ///     .add(
+    ///         Migration::new(2, "create_authors")
+    ///             .sql("CREATE TABLE authors (name TEXT);")
+    ///             .down_sql("DROP TABLE authors;"),
+    ///     );
+    ///
+    /// assert_eq!(vec![1, 2], migrator.migrate(&conn)?);
+    /// assert_eq!(vec![2, 1], migrator.migrate_to(&conn, 0)?);
+    /// assert_eq!(vec![1], migrator.migrate_to(&conn, 1)?);
+    ///
+    /// let states: Vec<_> = migrator.status(&conn)?.iter().map(|s| s.state()).collect();
+    /// assert_eq!(vec![MigrationState::Applied, MigrationState::Pending], states);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn migrate_to(&self, conn: &impl Executor, version: i64) -> Result<Vec<i64>, Error> {
+        self.check_order()?;
+        self.create_table(conn)?;
+
+        let applied = self.applied(conn)?;
+        let mut reverts = Vec::new();
+        for record in applied.iter().rev() {
+            let migration = self.find(record.version);
+            if let Some(migration) = migration {
+                if migration.checksum()? != record.checksum {
+                    return Err(Error::Migration(format!(
+                        "Checksum of applied migration {} changed",
+                        migration.label()
+                    )));
+                }
+            }
+            if record.version <= version {
+                continue;
+            }
+            match migration {
+                Some(migration) if migration.is_reversible() => reverts.push(migration),
+                Some(migration) => {
+                    return Err(Error::Migration(format!(
+                        "Migration {} has no down steps",
+                        migration.label()
+                    )))
+                }
+                None => {
+                    return Err(Error::Migration(format!(
+                        "Applied migration {} ({}) is unknown",
+                        record.version, record.name
+                    )))
+                }
+            }
+        }
+
+        let mut done = Vec::new();
+        for migration in reverts {
+            if migration.revert(conn, &self.table)? {
+                done.push(migration.version);
+            }
+        }
+        for migration in self.migrations.iter().filter(|m| m.version <= version) {
+            if applied
+                .iter()
+                .any(|record| record.version == migration.version)
+            {
+                continue;
+            }
+            if migration.apply(conn, &self.table)? {
+                done.push(migration.version);
+            }
+        }
+        Ok(done)
+    }
+
+    /// Status of known and applied migrations in order of versions.
+    /// Doesn't create bookkeeping table.
+    pub fn status(&self, conn: &impl Executor) -> Result<Vec<MigrationStatus>, Error> {
+        let mut db = Sqlite3Builder::select_from("sqlite_master");
+        let table = db.param(&self.table);
+        let exists = db
+            .field("COUNT(*)")
+            .and_where_eq("type", "'table'")
+            .and_where_eq("name", &table)
+            .get_i64(conn)?;
+        let applied = if exists > 0 {
+            self.applied(conn)?
+        } else {
+            Vec::new()
+        };
+
+        let mut status = Vec::new();
+        for migration in &self.migrations {
+            let record = applied.iter().find(|r| r.version == migration.version);
+            let state = match record {
+                None => MigrationState::Pending,
+                Some(record) if migration.checksum()? == record.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+            };
+            status.push(MigrationStatus {
+                version: migration.version,
+                name: migration.name.clone(),
+                state,
+                applied_at: record.map(|r| r.applied_at.clone()),
+            });
+        }
+        for record in applied {
+            if self.find(record.version).is_none() {
+                status.push(MigrationStatus {
+                    version: record.version,
+                    name: record.name,
+                    state: MigrationState::Unknown,
+                    applied_at: Some(record.applied_at),
+                });
+            }
+        }
+        status.sort_by_key(MigrationStatus::version);
+        Ok(status)
+    }
+
+    /// Find migration by version
+    fn find(&self, version: i64) -> Option<&Migration> {
+        self.migrations.iter().find(|m| m.version == version)
+    }
+
+    /// Check that versions increase
+    fn check_order(&self) -> Result<(), Error> {
+        for pair in self.migrations.windows(2) {
+            if pair[0].version >= pair[1].version {
+                return Err(Error::Migration(format!(
+                    "Migration {} follows {}, versions must increase",
+                    pair[1].label(),
+                    pair[0].label()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Create bookkeeping table if it doesn't exist
+    fn create_table(&self, conn: &impl Executor) -> Result<(), Error> {
+        Sqlite3Builder::create_table(&self.table)
+            .if_not_exists()
+            .column(Column::new("version", "INTEGER").primary_key())
+            .column(Column::new("name", "TEXT").not_null())
+            .column(Column::new("checksum", "TEXT").not_null())
+            .column(
+                Column::new("applied_at", "TEXT")
+                    .not_null()
+                    .default("CURRENT_TIMESTAMP"),
+            )
+            .exec(conn)
+    }
+
+    /// Records of applied migrations
+    fn applied(&self, conn: &impl Executor) -> Result<Vec<Applied>, Error> {
+        Sqlite3Builder::select_from(&self.table)
+            .fields(&["version", "name", "checksum", "applied_at"])
+            .order_asc("version")
+            .get_as(conn)
+    }
+}
+
+/// Split `NNNN_name.sql` or `NNNN_name.down.sql` into version, name and down flag
+fn parse_file_name(file_name: &str) -> Result<(i64, &str, bool), Error> {
+    let stem = file_name.trim_end_matches(".sql");
+    let (stem, down) = match stem.strip_suffix(".down") {
+        Some(stem) => (stem, true),
+        None => (stem, false),
+    };
+    let (version, name) = match stem.find('_') {
+        Some(pos) => (&stem[..pos], &stem[pos + 1..]),
+        None => (stem, ""),
+    };
+    match version.parse::<i64>() {
+        Ok(version) if !name.is_empty() && version.is_positive() => Ok((version, name, down)),
+        _ => Err(Error::Migration(format!(
+            "Migration file {} is not named NNNN_name.sql",
+            file_name
+        ))),
+    }
+}
+
+/// FNV-1a hash of text, stable between builds
+fn checksum(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_migrate() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let counter = runs.clone();
+        let mut migrator = Migrator::new();
+        migrator
+            .table("versions")
+            .add(Migration::new(1, "create_books").sql(
+                "CREATE TABLE books (title TEXT NOT NULL); \
+                 INSERT INTO books VALUES ('Dune');",
+            ))
+            .add(
+                Migration::new(3, "add_price")
+                    .statement(
+                        Sqlite3Builder::alter_table("books")
+                            .add_column(Column::new("price", "INTEGER").default(0)),
+                    )
+                    .run(move |tx| {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        Sqlite3Builder::update_table("books")
+                            .set("price", 100)
+                            .exec(tx)?;
+                        Ok(())
+                    }),
+            );
+        assert_eq!(migrator.migrate(&conn)?, vec![1, 3]);
+        assert_eq!(migrator.migrate(&conn)?, Vec::<i64>::new());
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let price = Sqlite3Builder::select_from("books")
+            .field("price")
+            .get_i64(&conn)?;
+        assert_eq!(price, 100);
+
+        let applied = migrator.applied(&conn)?;
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[0].checksum, migrator.migrations()[0].checksum()?);
+
+        migrator.add(Migration::new(2, "late").sql("SELECT 1;"));
+        let err = migrator.migrate(&conn).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Migration 2 (late) follows 3 (add_price), versions must increase"
+        );
+
+        let mut changed = Migrator::new();
+        changed
+            .table("versions")
+            .add(Migration::new(1, "create_books").sql("CREATE TABLE books (title TEXT);"))
+            .add(Migration::new(4, "drop_books").sql("DROP TABLE books;"));
+        let err = changed.migrate(&conn).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Checksum of applied migration 1 (create_books) changed"
+        );
+
+        let mut failing = Migrator::new();
+        failing.table("versions").add(
+            Migration::new(5, "broken")
+                .sql("CREATE TABLE authors (name TEXT);")
+                .sql("INSERT INTO missing VALUES (1);"),
+        );
+        assert!(failing.migrate(&conn).is_err());
+        let authors = Sqlite3Builder::select_from("sqlite_master")
+            .field("COUNT(*)")
+            .and_where_eq("name", "'authors'")
+            .get_i64(&conn)?;
+        assert_eq!(authors, 0);
+
+        let mut db = Sqlite3Builder::delete_from("books");
+        let title = db.param("Dune");
+        db.and_where_eq("title", &title);
+        let mut param = Migration::new(6, "param");
+        param
+            .statement(Sqlite3Builder::create_index("idx_books_title", "books").column("title"))
+            .statement(&db);
+        let err = param.script().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Migration 6 (param): Parameters are not allowed in SQL text"
+        );
+
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        assert_send_sync(&migrator);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_to() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+        let count = |table: &str| -> Result<i64, Error> {
+            Sqlite3Builder::select_from("sqlite_master")
+                .field("COUNT(*)")
+                .and_where_eq("name", quote(table))
+                .get_i64(&conn)
+        };
+
+        let mut migrator = Migrator::new();
+        migrator
+            .add(
+                Migration::new(1, "create_books")
+                    .sql("CREATE TABLE books (title TEXT);")
+                    .down_statement(&Sqlite3Builder::select_values(&["1"]))
+                    .down_sql("DROP TABLE books;"),
+            )
+            .add(
+                Migration::new(2, "create_authors")
+                    .sql("CREATE TABLE authors (name TEXT);")
+                    .down_run(|tx| Sqlite3Builder::drop_table("authors").exec(tx)),
+            )
+            .add(
+                Migration::new(3, "seed").statement(
+                    Sqlite3Builder::insert_into("books")
+                        .field("title")
+                        .values(&["'Dune'"]),
+                ),
+            );
+
+        let status = migrator.status(&conn)?;
+        assert_eq!(status.len(), 3);
+        assert!(status
+            .iter()
+            .all(|s| s.state() == MigrationState::Pending && s.applied_at().is_none()));
+        assert_eq!(count(DEFAULT_MIGRATIONS_TABLE)?, 0);
+
+        assert_eq!(migrator.migrate_to(&conn, 2)?, vec![1, 2]);
+        assert_eq!(count("authors")?, 1);
+        assert_eq!(migrator.migrate_to(&conn, 2)?, Vec::<i64>::new());
+        assert_eq!(migrator.migrate_to(&conn, 1)?, vec![2]);
+        assert_eq!(count("authors")?, 0);
+        assert_eq!(migrator.migrate(&conn)?, vec![2, 3]);
+
+        let err = migrator.migrate_to(&conn, 0).unwrap_err();
+        assert_eq!(err.to_string(), "Migration 3 (seed) has no down steps");
+        assert_eq!(count("books")?, 1);
+
+        let mut changed = Migrator::new();
+        changed
+            .add(Migration::new(1, "create_books").sql("CREATE TABLE books (isbn TEXT);"))
+            .add(Migration::new(4, "extra").sql("SELECT 1;"));
+        let status = changed
+            .status(&conn)?
+            .iter()
+            .map(|s| (s.version(), s.name().to_string(), s.state()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            status,
+            vec![
+                (1, "create_books".to_string(), MigrationState::Modified),
+                (2, "create_authors".to_string(), MigrationState::Unknown),
+                (3, "seed".to_string(), MigrationState::Unknown),
+                (4, "extra".to_string(), MigrationState::Pending),
+            ]
+        );
+        assert!(changed.status(&conn)?[0].applied_at().is_some());
+
+        let mut unknown = Migrator::new();
+        unknown.add(migrator.migrations()[0].clone().down_sql(""));
+        let err = unknown.migrate_to(&conn, 1).unwrap_err();
+        assert_eq!(err.to_string(), "Applied migration 3 (seed) is unknown");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_dir() -> Result<(), Error> {
+        let dir = std::env::temp_dir().join(format!("sqlite3builder_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("0002_add_price.sql"),
+            "ALTER TABLE books ADD price;",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("0001_create_books.sql"),
+            "CREATE TABLE books (title);",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("0002_add_price.down.sql"),
+            "ALTER TABLE books DROP price;",
+        )
+        .unwrap();
+        fs::write(dir.join("README.md"), "Migrations").unwrap();
+
+        let mut migrator = Migrator::new();
+        migrator.load_dir(&dir)?;
+        let names = migrator
+            .migrations()
+            .iter()
+            .map(|m| (m.version(), m.name().to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                (1, "create_books".to_string()),
+                (2, "add_price".to_string())
+            ]
+        );
+
+        let conn = sqlite3::open(":memory:")?;
+        assert_eq!(migrator.migrate(&conn)?, vec![1, 2]);
+        assert!(!migrator.migrations()[0].is_reversible());
+        assert_eq!(migrator.migrate_to(&conn, 1)?, vec![2]);
+
+        fs::write(dir.join("0003_orphan.down.sql"), "SELECT 1;").unwrap();
+        let err = Migrator::new().load_dir(&dir).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Down migration 3_orphan has no up migration"
+        );
+        fs::remove_file(dir.join("0003_orphan.down.sql")).unwrap();
+
+        fs::write(dir.join("latest.sql"), "SELECT 1;").unwrap();
+        let err = Migrator::new().load_dir(&dir).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Migration file latest.sql is not named NNNN_name.sql"
+        );
+
+        assert!(Migrator::new().load_dir(&dir).is_err());
+        assert_eq!(checksum(""), "cbf29ce484222325");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Typed access to result rows.
+
+use crate::error::Error;
+use crate::value::FromValue;
+use sqlite3::Value as SValue;
+
+/// Result row with column names
+pub struct Row<'a> {
+    columns: &'a [String],
+    values: &'a [SValue],
+}
+
+impl<'a> Row<'a> {
+    /// Create row from column names and values
+    pub fn new(columns: &'a [String], values: &'a [SValue]) -> Self {
+        Self { columns, values }
+    }
+
+    /// Number of columns
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// True if row has no columns
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Column names
+    pub fn columns(&self) -> &'a [String] {
+        self.columns
+    }
+
+    /// Raw column values
+    pub fn values(&self) -> &'a [SValue] {
+        self.values
+    }
+
+    /// Get column value by index
+    pub fn get<T: FromValue>(&self, idx: usize) -> Result<T, Error> {
+        let name = self.columns.get(idx).map_or("", String::as_str);
+        let value = self
+            .values
+            .get(idx)
+            .ok_or_else(|| Error::conversion("no such column").at_column(idx, name))?;
+        T::from_value(value).map_err(|err| err.at_column(idx, name))
+    }
+
+    /// Get column value by name
+    pub fn get_named<T: FromValue>(&self, name: &str) -> Result<T, Error> {
+        match self.columns.iter().position(|col| col == name) {
+            Some(idx) => self.get(idx),
+            None => Err(Error::Conversion {
+                index: None,
+                name: Some(name.to_string()),
+                message: "no such column".to_string(),
+            }),
+        }
+    }
+}
+
+/// Type which may be built from a result row
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// use sqlite3builder::{Error, FromRow, Row};
+///
+/// struct Book {
+///     title: String,
+///     price: Option<i64>,
+/// }
+///
+/// impl FromRow for Book {
+///     fn from_row(row: &Row) -> Result<Self, Error> {
+///         Ok(Self {
+///             title: row.get_named("title")?,
+///             price: row.get_named("price")?,
+///         })
+///     }
+/// }
+/// ```
+pub trait FromRow: Sized {
+    /// Convert row to value
+    fn from_row(row: &Row) -> Result<Self, Error>;
+}
+
+macro_rules! tuple_from_row {
+    ($($t:ident $idx:tt),+) => {
+        impl<$($t: FromValue),+> FromRow for ($($t,)+) {
+            fn from_row(row: &Row) -> Result<Self, Error> {
+                Ok(($(row.get::<$t>($idx)?,)+))
+            }
+        }
+    };
+}
+
+tuple_from_row!(A 0);
+tuple_from_row!(A 0, B 1);
+tuple_from_row!(A 0, B 1, C 2);
+tuple_from_row!(A 0, B 1, C 2, D 3);
+tuple_from_row!(A 0, B 1, C 2, D 3, E 4);
+tuple_from_row!(A 0, B 1, C 2, D 3, E 4, F 5);
+tuple_from_row!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple_from_row!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tuple_from_row() -> Result<(), Error> {
+        let columns = vec!["title".to_string(), "price".to_string()];
+        let values = vec![SValue::String("Dune".to_string()), SValue::Null];
+        let row = Row::new(&columns, &values);
+
+        let (title, price) = <(String, Option<i64>)>::from_row(&row)?;
+        assert_eq!(title, "Dune");
+        assert_eq!(price, None);
+
+        let err = <(String, i64)>::from_row(&row).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Column 1 (price): Expected Integer, found Null"
+        );
+
+        let err = row.get_named::<i64>("author").err().unwrap();
+        assert_eq!(err.to_string(), "Column author: no such column");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,586 @@
+//! Schema introspection.
+//!
+//! Reads `sqlite_schema` table, also known as `sqlite_master`, and table PRAGMAs of main database.
+//!
+//! ```
+//! extern crate sqlite3builder;
+//!
+//! # use std::error::Error;
+//! use sqlite3builder::schema::Schema;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let conn = sqlite3::open(":memory:")?;
+//! conn.execute("CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT NOT NULL);")?;
+//!
+//! let schema = Schema::read(&conn)?;
+//! let books = schema.table("books").unwrap();
+//! let title = books.column("title").unwrap();
+//!
+//! assert_eq!("TEXT", title.kind());
+//! assert!(title.not_null());
+//! assert_eq!(vec!["id"], books.primary_key());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Error;
+use crate::executor::Executor;
+use crate::row::{FromRow, Row};
+use crate::{quote, Sqlite3Builder};
+use std::path::Path;
+
+mod diff;
+
+pub use diff::{Change, SchemaDiff, TableChange, TableDiff};
+
+/// Find object by name, ignoring ASCII case like SQLite does
+fn find<'a, T, F>(items: &'a [T], name: &str, item_name: F) -> Option<&'a T>
+where
+    F: Fn(&T) -> &str,
+{
+    items
+        .iter()
+        .find(|item| item_name(item).eq_ignore_ascii_case(name))
+}
+
+/// Column of table or view
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Column {
+    name: String,
+    kind: String,
+    not_null: bool,
+    default: Option<String>,
+    primary_key: usize,
+    hidden: i64,
+}
+
+impl Column {
+    /// Column name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Declared type, empty if there is none
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// True if column is NOT NULL
+    pub fn not_null(&self) -> bool {
+        self.not_null
+    }
+
+    /// Text of DEFAULT expression
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// Position in PRIMARY KEY starting from 1, or 0 if column isn't part of it
+    pub fn primary_key(&self) -> usize {
+        self.primary_key
+    }
+
+    /// True if column is generated
+    pub fn is_generated(&self) -> bool {
+        self.hidden == 2 || self.hidden == 3
+    }
+
+    /// True if column is hidden column of virtual table
+    pub fn is_hidden(&self) -> bool {
+        self.hidden == 1
+    }
+}
+
+impl FromRow for Column {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        let hidden = if row.columns().iter().any(|col| col == "hidden") {
+            row.get_named("hidden")?
+        } else {
+            0
+        };
+        Ok(Self {
+            name: row.get_named("name")?,
+            kind: row.get_named("type")?,
+            not_null: row.get_named("notnull")?,
+            default: row.get_named("dflt_value")?,
+            primary_key: row.get_named::<i64>("pk")? as usize,
+            hidden,
+        })
+    }
+}
+
+/// Index of table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Index {
+    name: String,
+    unique: bool,
+    origin: String,
+    partial: bool,
+    columns: Vec<Option<String>>,
+    sql: Option<String>,
+}
+
+impl Index {
+    /// Index name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// True if index is UNIQUE
+    pub fn unique(&self) -> bool {
+        self.unique
+    }
+
+    /// True if index was created by CREATE INDEX,
+    /// false for indexes of UNIQUE and PRIMARY KEY constraints
+    pub fn is_explicit(&self) -> bool {
+        self.origin == "c"
+    }
+
+    /// True if index has WHERE clause
+    pub fn partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Indexed columns, None for expressions
+    pub fn columns(&self) -> &[Option<String>] {
+        &self.columns
+    }
+
+    /// CREATE INDEX statement, None for indexes of constraints
+    pub fn sql(&self) -> Option<&str> {
+        self.sql.as_deref()
+    }
+}
+
+/// Foreign key of table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForeignKey {
+    table: String,
+    from: Vec<String>,
+    to: Vec<Option<String>>,
+    on_update: String,
+    on_delete: String,
+}
+
+impl ForeignKey {
+    /// Parent table
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Child columns
+    pub fn from(&self) -> &[String] {
+        &self.from
+    }
+
+    /// Parent columns, None refers to primary key of parent table
+    pub fn to(&self) -> &[Option<String>] {
+        &self.to
+    }
+
+    /// ON UPDATE action, `NO ACTION` by default
+    pub fn on_update(&self) -> &str {
+        &self.on_update
+    }
+
+    /// ON DELETE action, `NO ACTION` by default
+    pub fn on_delete(&self) -> &str {
+        &self.on_delete
+    }
+}
+
+/// Table with its columns, indexes and foreign keys
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    name: String,
+    sql: String,
+    columns: Vec<Column>,
+    indexes: Vec<Index>,
+    foreign_keys: Vec<ForeignKey>,
+}
+
+impl Table {
+    /// Table name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// CREATE TABLE statement
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Columns in order of definition
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Find column by name
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        find(&self.columns, name, Column::name)
+    }
+
+    /// Names of PRIMARY KEY columns in order of key
+    pub fn primary_key(&self) -> Vec<&str> {
+        let mut columns = self
+            .columns
+            .iter()
+            .filter(|col| col.primary_key > 0)
+            .collect::<Vec<&Column>>();
+        columns.sort_by_key(|col| col.primary_key);
+        columns.into_iter().map(Column::name).collect()
+    }
+
+    /// Indexes ordered by name
+    pub fn indexes(&self) -> &[Index] {
+        &self.indexes
+    }
+
+    /// Find index by name
+    pub fn index(&self, name: &str) -> Option<&Index> {
+        find(&self.indexes, name, Index::name)
+    }
+
+    /// Foreign keys in order of definition
+    pub fn foreign_keys(&self) -> &[ForeignKey] {
+        &self.foreign_keys
+    }
+}
+
+/// View with its columns
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct View {
+    name: String,
+    sql: String,
+    columns: Vec<Column>,
+}
+
+impl View {
+    /// View name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// CREATE VIEW statement
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Result columns
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+}
+
+/// Trigger on table or view
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trigger {
+    name: String,
+    table: String,
+    sql: String,
+}
+
+impl Trigger {
+    /// Trigger name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Table or view the trigger is attached to
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// CREATE TRIGGER statement
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+}
+
+/// Tables, views and triggers of database, ordered by name.
+/// Internal `sqlite_` tables are skipped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schema {
+    tables: Vec<Table>,
+    views: Vec<View>,
+    triggers: Vec<Trigger>,
+}
+
+impl Schema {
+    /// Read schema of main database
+    pub fn read(conn: &impl Executor) -> Result<Self, Error> {
+        let objects = Sqlite3Builder::select_from("sqlite_master")
+            .fields(&["type", "name", "tbl_name", "sql"])
+            .and_where("type IN ('table', 'view', 'trigger')")
+            .and_where("substr(name, 1, 7) <> 'sqlite_'")
+            .and_where_is_not_null("sql")
+            .order_asc("name")
+            .get_as::<(String, String, String, String)>(conn)?;
+
+        let mut schema = Self::default();
+        for (kind, name, table, sql) in objects {
+            match kind.as_str() {
+                "table" => schema.tables.push(Table {
+                    columns: read_columns(conn, &name)?,
+                    indexes: read_indexes(conn, &name)?,
+                    foreign_keys: read_foreign_keys(conn, &name)?,
+                    name,
+                    sql,
+                }),
+                "view" => schema.views.push(View {
+                    columns: read_columns(conn, &name)?,
+                    name,
+                    sql,
+                }),
+                _ => schema.triggers.push(Trigger { name, table, sql }),
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Read schema of database file, which must exist
+    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(Error::Build(format!(
+                "No such database file: {}",
+                path.display()
+            )));
+        }
+        Self::read(&sqlite3::open(path)?)
+    }
+
+    /// Build schema declared by DDL statements, e.g. made by `create_table` and `create_index`
+    pub fn from_sql<S: AsRef<str>>(statements: &[S]) -> Result<Self, Error> {
+        let conn = sqlite3::open(":memory:")?;
+        for sql in statements {
+            conn.execute(sql.as_ref())?;
+        }
+        Self::read(&conn)
+    }
+
+    /// Difference turning this schema into target one
+    pub fn diff(&self, target: &Schema) -> SchemaDiff {
+        SchemaDiff::new(self, target)
+    }
+
+    /// Tables ordered by name
+    pub fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+
+    /// Find table by name
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        find(&self.tables, name, Table::name)
+    }
+
+    /// Views ordered by name
+    pub fn views(&self) -> &[View] {
+        &self.views
+    }
+
+    /// Find view by name
+    pub fn view(&self, name: &str) -> Option<&View> {
+        find(&self.views, name, View::name)
+    }
+
+    /// Triggers ordered by name
+    pub fn triggers(&self) -> &[Trigger] {
+        &self.triggers
+    }
+
+    /// Find trigger by name
+    pub fn trigger(&self, name: &str) -> Option<&Trigger> {
+        find(&self.triggers, name, Trigger::name)
+    }
+
+    /// Check that table or view has all columns, for validating builders before execution
+    pub fn check_columns<S: AsRef<str>>(&self, table: &str, columns: &[S]) -> Result<(), Error> {
+        let existing = match (self.table(table), self.view(table)) {
+            (Some(table), _) => table.columns(),
+            (None, Some(view)) => view.columns(),
+            (None, None) => return Err(Error::Build(format!("No such table: {}", table))),
+        };
+        for column in columns {
+            let column = column.as_ref();
+            if find(existing, column, Column::name).is_none() {
+                return Err(Error::Build(format!(
+                    "No such column: {}.{}",
+                    table, column
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read columns by PRAGMA table_xinfo, or table_info before SQLite 3.26.0
+fn read_columns(conn: &impl Executor, table: &str) -> Result<Vec<Column>, Error> {
+    let pragma = if sqlite3::version() >= 3_026_000 {
+        "pragma_table_xinfo"
+    } else {
+        "pragma_table_info"
+    };
+    Sqlite3Builder::select_from(format!("{}({})", pragma, quote(table)))
+        .order_asc("cid")
+        .get_as(conn)
+}
+
+/// Read indexes by PRAGMA index_list and index_info
+fn read_indexes(conn: &impl Executor, table: &str) -> Result<Vec<Index>, Error> {
+    let list = Sqlite3Builder::select_from(format!("pragma_index_list({})", quote(table)))
+        .fields(&["name", "\"unique\"", "origin", "partial"])
+        .order_asc("name")
+        .get_as::<(String, bool, String, bool)>(conn)?;
+
+    let mut indexes = Vec::new();
+    for (name, unique, origin, partial) in list {
+        let columns = Sqlite3Builder::select_from(format!("pragma_index_info({})", quote(&name)))
+            .field("name")
+            .order_asc("seqno")
+            .get_as::<(Option<String>,)>(conn)?
+            .into_iter()
+            .map(|(column,)| column)
+            .collect();
+
+        let mut db = Sqlite3Builder::select_from("sqlite_master");
+        let index = db.param(&name);
+        let sql = db
+            .field("sql")
+            .and_where_eq("type", "'index'")
+            .and_where_eq("name", &index)
+            .get_as::<(Option<String>,)>(conn)?
+            .into_iter()
+            .next()
+            .and_then(|(sql,)| sql);
+
+        indexes.push(Index {
+            name,
+            unique,
+            origin,
+            partial,
+            columns,
+            sql,
+        });
+    }
+    Ok(indexes)
+}
+
+/// Read foreign keys by PRAGMA foreign_key_list, grouping their columns
+fn read_foreign_keys(conn: &impl Executor, table: &str) -> Result<Vec<ForeignKey>, Error> {
+    let rows = Sqlite3Builder::select_from(format!("pragma_foreign_key_list({})", quote(table)))
+        .fields(&[
+            "id",
+            "\"table\"",
+            "\"from\"",
+            "\"to\"",
+            "on_update",
+            "on_delete",
+        ])
+        .order_asc("id")
+        .order_asc("seq")
+        .get_as::<(i64, String, String, Option<String>, String, String)>(conn)?;
+
+    let mut keys: Vec<(i64, ForeignKey)> = Vec::new();
+    for (id, parent, from, to, on_update, on_delete) in rows {
+        match keys.last_mut() {
+            Some((last, key)) if *last == id => {
+                key.from.push(from);
+                key.to.push(to);
+            }
+            _ => keys.push((
+                id,
+                ForeignKey {
+                    table: parent,
+                    from: vec![from],
+                    to: vec![to],
+                    on_update,
+                    on_delete,
+                },
+            )),
+        }
+    }
+    // PRAGMA numbers foreign keys from the last defined one
+    Ok(keys.into_iter().rev().map(|(_, key)| key).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_schema() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+        conn.execute(
+            "CREATE TABLE authors (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT UNIQUE); \
+             CREATE TABLE books ( \
+                 author INTEGER NOT NULL REFERENCES authors ON DELETE CASCADE, \
+                 title TEXT COLLATE NOCASE, \
+                 price INTEGER DEFAULT 0, \
+                 cents INTEGER GENERATED ALWAYS AS (price * 100), \
+                 editor INTEGER, \
+                 PRIMARY KEY (title, author), \
+                 FOREIGN KEY (editor) REFERENCES authors (id)); \
+             CREATE INDEX idx_books_price ON books (price, lower(title)) WHERE price > 0; \
+             CREATE VIEW cheap AS SELECT title, price FROM books WHERE price < 100; \
+             CREATE TRIGGER trg_cheap INSTEAD OF DELETE ON cheap \
+                 BEGIN DELETE FROM books WHERE title = OLD.title; END; \
+             CREATE TABLE sqlite1 (id INTEGER);",
+        )?;
+
+        let schema = Schema::read(&conn)?;
+        let tables = schema.tables().iter().map(Table::name).collect::<Vec<_>>();
+        assert_eq!(tables, vec!["authors", "books", "sqlite1"]);
+
+        let books = schema.table("BOOKS").unwrap();
+        assert_eq!(books.columns().len(), 5);
+        assert_eq!(books.primary_key(), vec!["title", "author"]);
+
+        let author = books.column("author").unwrap();
+        assert_eq!(author.kind(), "INTEGER");
+        assert!(author.not_null());
+        assert_eq!(author.primary_key(), 2);
+        assert_eq!(books.column("price").unwrap().default(), Some("0"));
+        assert!(books.column("cents").unwrap().is_generated());
+        assert!(!books.column("price").unwrap().is_generated());
+
+        let indexes = books.indexes();
+        assert_eq!(indexes.len(), 2);
+        let price = books.index("idx_books_price").unwrap();
+        assert!(price.is_explicit() && price.partial() && !price.unique());
+        assert_eq!(price.columns(), &[Some("price".to_string()), None]);
+        assert!(price
+            .sql()
+            .unwrap()
+            .starts_with("CREATE INDEX idx_books_price"));
+        let pk = indexes.iter().find(|index| !index.is_explicit()).unwrap();
+        assert!(pk.unique() && !pk.is_explicit() && pk.sql().is_none());
+
+        let keys = books.foreign_keys();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].table(), "authors");
+        assert_eq!(keys[0].from(), &["author".to_string()]);
+        assert_eq!(keys[0].to(), &[None]);
+        assert_eq!(keys[0].on_delete(), "CASCADE");
+        assert_eq!(keys[1].to(), &[Some("id".to_string())]);
+        assert_eq!(keys[1].on_update(), "NO ACTION");
+
+        let cheap = schema.view("cheap").unwrap();
+        let columns = cheap.columns().iter().map(Column::name).collect::<Vec<_>>();
+        assert_eq!(columns, vec!["title", "price"]);
+
+        let trigger = schema.trigger("trg_cheap").unwrap();
+        assert_eq!(trigger.table(), "cheap");
+        assert!(schema.table("sqlite_sequence").is_none());
+
+        schema.check_columns("books", &["title", "Price"])?;
+        schema.check_columns("cheap", &["title"])?;
+        let err = schema.check_columns("books", &["isbn"]).unwrap_err();
+        assert_eq!(err.to_string(), "No such column: books.isbn");
+        let err = schema.check_columns("shelves", &["id"]).unwrap_err();
+        assert_eq!(err.to_string(), "No such table: shelves");
+
+        Ok(())
+    }
+}
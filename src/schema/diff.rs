@@ -0,0 +1,644 @@
+//! Difference between two schemas and script reconciling them.
+
+use super::{Column, Index, Schema, Table, Trigger, View};
+use crate::alter::{new_table_name, rebuild_statements, run_rebuild};
+use crate::error::Error;
+use crate::executor::Executor;
+
+/// Change of schema object
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change<T> {
+    /// Object exists in target schema only
+    Added(T),
+    /// Object exists in source schema only
+    Removed(T),
+    /// Object differs, source and target versions
+    Changed(T, T),
+}
+
+/// Changes of table existing in both schemas
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableDiff {
+    from: Table,
+    to: Table,
+    columns: Vec<Change<Column>>,
+    indexes: Vec<Change<Index>>,
+    rebuild: bool,
+}
+
+impl TableDiff {
+    /// Table name in target schema
+    pub fn name(&self) -> &str {
+        self.to.name()
+    }
+
+    /// Table in source schema
+    pub fn from(&self) -> &Table {
+        &self.from
+    }
+
+    /// Table in target schema
+    pub fn to(&self) -> &Table {
+        &self.to
+    }
+
+    /// Changed columns
+    pub fn columns(&self) -> &[Change<Column>] {
+        &self.columns
+    }
+
+    /// Changed indexes created by CREATE INDEX
+    pub fn indexes(&self) -> &[Change<Index>] {
+        &self.indexes
+    }
+
+    /// True if table definition changed and table must be rebuilt
+    pub fn needs_rebuild(&self) -> bool {
+        self.rebuild
+    }
+}
+
+/// Change of table
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableChange {
+    /// Table exists in target schema only
+    Added(Table),
+    /// Table exists in source schema only
+    Removed(Table),
+    /// Table differs in columns, constraints or indexes
+    Altered(TableDiff),
+}
+
+/// Difference between source and target schema.
+/// SQL of objects is compared ignoring whitespace.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::schema::Schema;
+/// use sqlite3builder::{Column, Sqlite3Builder};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let conn = sqlite3::open(":memory:")?;
+/// conn.execute("CREATE TABLE books (title TEXT);")?;
+///
+/// let declared = Schema::from_sql(&[
+///     Sqlite3Builder::create_table("books")
+///         .column(&Column::new("title", "TEXT"))
+///         .sql()?,
+///     Sqlite3Builder::create_index("idx_books_title", "books")
+///         .column("title")
+///         .sql()?,
+/// ])?;
+///
+/// let diff = Schema::read(&conn)?.diff(&declared);
+/// assert_eq!("PRAGMA foreign_keys = OFF;\n\
+///     BEGIN;\n\
+///     CREATE INDEX idx_books_title ON books (title);\n\
+///     PRAGMA foreign_key_check;\n\
+///     COMMIT;\n\
+///     PRAGMA foreign_keys = ON;", &diff.sql());
+///
+/// diff.exec(&conn)?;
+/// assert!(Schema::read(&conn)?.diff(&declared).is_empty());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    tables: Vec<TableChange>,
+    views: Vec<Change<View>>,
+    triggers: Vec<Change<Trigger>>,
+    kept_triggers: Vec<Trigger>,
+}
+
+impl SchemaDiff {
+    /// Compare source schema with target one
+    pub fn new(from: &Schema, to: &Schema) -> Self {
+        let mut tables = Vec::new();
+        for table in from.tables() {
+            if to.table(table.name()).is_none() {
+                tables.push(TableChange::Removed(table.clone()));
+            }
+        }
+        for table in to.tables() {
+            match from.table(table.name()) {
+                None => tables.push(TableChange::Added(table.clone())),
+                Some(old) => {
+                    let diff = diff_table(old, table);
+                    if diff.rebuild || !diff.columns.is_empty() || !diff.indexes.is_empty() {
+                        tables.push(TableChange::Altered(diff));
+                    }
+                }
+            }
+        }
+
+        let views = diff_objects(from.views(), to.views(), View::name, View::sql);
+        let triggers = diff_objects(from.triggers(), to.triggers(), Trigger::name, Trigger::sql);
+
+        // Unchanged triggers are dropped together with rebuilt table or changed view
+        let mut recreated = Vec::new();
+        for change in &tables {
+            if let TableChange::Altered(diff) = change {
+                if diff.rebuild {
+                    recreated.push(diff.name());
+                }
+            }
+        }
+        for change in &views {
+            if let Change::Changed(_, view) = change {
+                recreated.push(view.name());
+            }
+        }
+        let kept_triggers = to
+            .triggers()
+            .iter()
+            .filter(|trigger| {
+                recreated
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(trigger.table()))
+            })
+            .filter(|trigger| match from.trigger(trigger.name()) {
+                Some(old) => normalize(old.sql()) == normalize(trigger.sql()),
+                None => false,
+            })
+            .cloned()
+            .collect();
+
+        Self {
+            tables,
+            views,
+            triggers,
+            kept_triggers,
+        }
+    }
+
+    /// True if schemas are the same
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty() && self.views.is_empty() && self.triggers.is_empty()
+    }
+
+    /// Added, removed and altered tables
+    pub fn tables(&self) -> &[TableChange] {
+        &self.tables
+    }
+
+    /// Added, removed and changed views
+    pub fn views(&self) -> &[Change<View>] {
+        &self.views
+    }
+
+    /// Added, removed and changed triggers
+    pub fn triggers(&self) -> &[Change<Trigger>] {
+        &self.triggers
+    }
+
+    /// Script turning source schema into target one, empty if schemas are the same.
+    /// Altered table definitions are applied by rebuilding tables
+    /// in transaction with foreign keys disabled.
+    /// Its `PRAGMA foreign_key_check` only reports violations and doesn't prevent commit,
+    /// `exec` rolls back on them.
+    pub fn sql(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+
+        let mut script = vec![
+            "PRAGMA foreign_keys = OFF;".to_string(),
+            "BEGIN;".to_string(),
+        ];
+        script.extend(self.statements());
+        script.push("PRAGMA foreign_key_check;".to_string());
+        script.push("COMMIT;".to_string());
+        script.push("PRAGMA foreign_keys = ON;".to_string());
+        script.join("\n")
+    }
+
+    /// Apply changes in transaction with foreign keys disabled,
+    /// failing if any foreign key is violated before commit.
+    /// It must not be executed inside another transaction.
+    pub fn exec(&self, conn: &impl Executor) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        run_rebuild(conn, |_| Ok(self.statements()))
+    }
+
+    /// Statements run inside transaction
+    fn statements(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+
+        for change in &self.triggers {
+            if let Change::Removed(trigger) | Change::Changed(trigger, _) = change {
+                statements.push(format!("DROP TRIGGER {};", quote_name(trigger.name())));
+            }
+        }
+        for change in &self.views {
+            if let Change::Removed(view) | Change::Changed(view, _) = change {
+                statements.push(format!("DROP VIEW {};", quote_name(view.name())));
+            }
+        }
+        for change in &self.tables {
+            if let TableChange::Removed(table) = change {
+                statements.push(format!("DROP TABLE {};", quote_name(table.name())));
+            }
+        }
+
+        for change in &self.tables {
+            match change {
+                TableChange::Added(table) => {
+                    statements.push(format!("{};", table.sql()));
+                    statements.extend(index_sql(table.indexes()));
+                }
+                TableChange::Altered(diff) if diff.rebuild => {
+                    statements.extend(rebuild_sql(diff));
+                }
+                TableChange::Altered(diff) => {
+                    for change in &diff.indexes {
+                        if let Change::Removed(index) | Change::Changed(index, _) = change {
+                            statements.push(format!("DROP INDEX {};", quote_name(index.name())));
+                        }
+                    }
+                    for change in &diff.indexes {
+                        if let Change::Added(index) | Change::Changed(_, index) = change {
+                            statements.extend(index_sql(std::slice::from_ref(index)));
+                        }
+                    }
+                }
+                TableChange::Removed(_) => {}
+            }
+        }
+
+        for change in &self.views {
+            if let Change::Added(view) | Change::Changed(_, view) = change {
+                statements.push(format!("{};", view.sql()));
+            }
+        }
+        for change in &self.triggers {
+            if let Change::Added(trigger) | Change::Changed(_, trigger) = change {
+                statements.push(format!("{};", trigger.sql()));
+            }
+        }
+        for trigger in &self.kept_triggers {
+            statements.push(format!("{};", trigger.sql()));
+        }
+        statements
+    }
+}
+
+/// Compare tables with the same name
+fn diff_table(from: &Table, to: &Table) -> TableDiff {
+    let columns = diff_columns(from.columns(), to.columns());
+    let explicit = |table: &Table| {
+        table
+            .indexes()
+            .iter()
+            .filter(|index| index.is_explicit())
+            .cloned()
+            .collect::<Vec<Index>>()
+    };
+    let indexes = diff_objects(&explicit(from), &explicit(to), Index::name, |index| {
+        index.sql().unwrap_or_default()
+    });
+    TableDiff {
+        from: from.clone(),
+        to: to.clone(),
+        columns,
+        indexes,
+        rebuild: definition(from.sql()) != definition(to.sql()),
+    }
+}
+
+/// Compare columns by name
+fn diff_columns(from: &[Column], to: &[Column]) -> Vec<Change<Column>> {
+    let find = |columns: &[Column], name: &str| {
+        columns
+            .iter()
+            .find(|col| col.name().eq_ignore_ascii_case(name))
+            .cloned()
+    };
+    let mut changes = Vec::new();
+    for column in from {
+        if find(to, column.name()).is_none() {
+            changes.push(Change::Removed(column.clone()));
+        }
+    }
+    for column in to {
+        match find(from, column.name()) {
+            None => changes.push(Change::Added(column.clone())),
+            Some(old) if &old != column => changes.push(Change::Changed(old, column.clone())),
+            Some(_) => {}
+        }
+    }
+    changes
+}
+
+/// Compare objects by name and SQL
+fn diff_objects<T, N, S>(from: &[T], to: &[T], name: N, sql: S) -> Vec<Change<T>>
+where
+    T: Clone,
+    N: Fn(&T) -> &str,
+    S: Fn(&T) -> &str,
+{
+    let find = |items: &[T], item_name: &str| {
+        items
+            .iter()
+            .find(|item| name(item).eq_ignore_ascii_case(item_name))
+            .cloned()
+    };
+    let mut changes = Vec::new();
+    for item in from {
+        if find(to, name(item)).is_none() {
+            changes.push(Change::Removed(item.clone()));
+        }
+    }
+    for item in to {
+        match find(from, name(item)) {
+            None => changes.push(Change::Added(item.clone())),
+            Some(old) if normalize(sql(&old)) != normalize(sql(item)) => {
+                changes.push(Change::Changed(old, item.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    changes
+}
+
+/// CREATE INDEX statements of explicit indexes
+fn index_sql(indexes: &[Index]) -> Vec<String> {
+    indexes
+        .iter()
+        .filter_map(Index::sql)
+        .map(|sql| format!("{};", sql))
+        .collect()
+}
+
+/// Statements rebuilding table with new definition and copying common columns
+fn rebuild_sql(diff: &TableDiff) -> Vec<String> {
+    let mut columns = Vec::new();
+    let mut values = Vec::new();
+    for column in diff.to.columns().iter().filter(|col| !col.is_generated()) {
+        let old = diff
+            .from
+            .columns()
+            .iter()
+            .find(|col| !col.is_generated() && col.name().eq_ignore_ascii_case(column.name()));
+        if let Some(old) = old {
+            columns.push(quote_name(column.name()));
+            values.push(quote_name(old.name()));
+        }
+    }
+
+    let new_name = quote_name(&new_table_name(diff.name()));
+    let (_, rest) = split_name(diff.to.sql().trim_start_matches("CREATE TABLE "));
+    let mut statements = rebuild_statements(
+        &quote_name(diff.name()),
+        &new_name,
+        format!("CREATE TABLE {}{};", new_name, rest),
+        &columns,
+        &values,
+        false,
+    );
+    statements.extend(index_sql(diff.to.indexes()));
+    statements
+}
+
+/// Name quoted as identifier
+fn quote_name(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Split quoted or bare name from the rest of statement
+fn split_name(sql: &str) -> (&str, &str) {
+    let end = match sql.chars().next() {
+        Some(quote @ '"') | Some(quote @ '`') => {
+            // Doubled quote stands for quote inside name
+            let mut chars = sql.char_indices().skip(1).peekable();
+            let mut end = None;
+            while let Some((pos, c)) = chars.next() {
+                if c == quote {
+                    if chars.peek().map(|&(_, next)| next) != Some(quote) {
+                        end = Some(pos + 1);
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            end
+        }
+        Some('[') => sql.find(']').map(|pos| pos + 1),
+        _ => sql.find(|c: char| c.is_whitespace() || c == '('),
+    };
+    sql.split_at(end.unwrap_or(sql.len()))
+}
+
+/// Table definition without name
+fn definition(sql: &str) -> String {
+    let (_, rest) = split_name(sql.trim_start_matches("CREATE TABLE "));
+    normalize(rest)
+}
+
+/// SQL with whitespace collapsed and removed around punctuation.
+/// Quoted strings and names are kept as is.
+fn normalize(sql: &str) -> String {
+    let mut text = String::new();
+    let mut space = false;
+    let mut quote = None;
+    for c in sql.trim().chars() {
+        if let Some(end) = quote {
+            // Doubled quote closes and reopens span, so it is kept too
+            if c == end {
+                quote = None;
+            }
+            text.push(c);
+            continue;
+        }
+        quote = match c {
+            '\'' | '"' | '`' => Some(c),
+            '[' => Some(']'),
+            _ => None,
+        };
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        let punct = matches!(c, '(' | ')' | ',' | ';');
+        let last_punct = matches!(
+            text.chars().last(),
+            Some('(') | Some(')') | Some(',') | Some(';')
+        );
+        if space && !punct && !last_punct && !text.is_empty() {
+            text.push(' ');
+        }
+        space = false;
+        text.push(c);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sqlite3Builder;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize("CREATE TABLE t (\n  a INT ,\n  b  TEXT\n)"),
+            "CREATE TABLE t(a INT,b TEXT)"
+        );
+        assert_eq!(
+            normalize("a  DEFAULT 'x  ( y'\n, \"b  c\" , [d  e],`f ''  g`"),
+            "a DEFAULT 'x  ( y',\"b  c\",[d  e],`f ''  g`"
+        );
+        assert_eq!(normalize("'it''s  ok'  ,b"), "'it''s  ok',b");
+        assert_ne!(
+            definition("CREATE TABLE t (a TEXT DEFAULT 'a  b')"),
+            definition("CREATE TABLE t (a TEXT DEFAULT 'a b')")
+        );
+        assert_eq!(split_name("\"my table\" (a)"), ("\"my table\"", " (a)"));
+        assert_eq!(split_name("books(a)"), ("books", "(a)"));
+        assert_eq!(split_name("\"a \"\"b\"\"\"(a)"), ("\"a \"\"b\"\"\"", "(a)"));
+        assert_eq!(
+            definition("CREATE TABLE \"books\"(a)"),
+            definition("CREATE TABLE books (a)")
+        );
+    }
+
+    #[test]
+    fn test_schema_diff() -> Result<(), Error> {
+        let dir = std::env::temp_dir();
+        let prod = dir.join(format!("sqlite3builder_prod_{}.db", std::process::id()));
+        let stage = dir.join(format!("sqlite3builder_stage_{}.db", std::process::id()));
+
+        let conn = sqlite3::open(&prod)?;
+        conn.execute(
+            "CREATE TABLE authors (id INTEGER PRIMARY KEY, name TEXT); \
+             CREATE TABLE books (id INTEGER PRIMARY KEY, author INTEGER REFERENCES authors, \
+                 title TEXT, price TEXT); \
+             CREATE TABLE shelves (id INTEGER PRIMARY KEY); \
+             CREATE INDEX idx_books_title ON books (title); \
+             CREATE INDEX idx_authors_name ON authors (name); \
+             CREATE VIEW cheap AS SELECT title FROM books WHERE price < 100; \
+             CREATE TRIGGER trg_books AFTER DELETE ON books BEGIN DELETE FROM shelves; END; \
+             CREATE TRIGGER trg_old AFTER INSERT ON authors BEGIN SELECT 1; END; \
+             INSERT INTO authors VALUES (1, 'Herbert'); \
+             INSERT INTO books VALUES (1, 1, 'Dune', '120');",
+        )?;
+        sqlite3::open(&stage)?.execute(
+            "CREATE TABLE authors (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT ''); \
+             CREATE TABLE books (id INTEGER PRIMARY KEY, author INTEGER REFERENCES authors, \
+                 title TEXT,   price INTEGER); \
+             CREATE TABLE reviews (book INTEGER REFERENCES books, body TEXT); \
+             CREATE INDEX idx_books_title ON books (title); \
+             CREATE INDEX idx_authors_name ON authors (name COLLATE NOCASE); \
+             CREATE VIEW cheap AS SELECT title, price FROM books WHERE price < 100; \
+             CREATE TRIGGER trg_books AFTER DELETE ON books BEGIN DELETE FROM shelves; END;",
+        )?;
+
+        let from = Schema::read_file(&prod)?;
+        let to = Schema::read_file(&stage)?;
+        let diff = from.diff(&to);
+        assert!(!diff.is_empty());
+
+        let tables = diff
+            .tables()
+            .iter()
+            .map(|change| match change {
+                TableChange::Added(table) => format!("+{}", table.name()),
+                TableChange::Removed(table) => format!("-{}", table.name()),
+                TableChange::Altered(diff) => format!(
+                    "~{} {} {} {}",
+                    diff.name(),
+                    diff.columns().len(),
+                    diff.indexes().len(),
+                    diff.needs_rebuild()
+                ),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tables,
+            vec![
+                "-shelves",
+                "~authors 1 1 true",
+                "~books 1 0 true",
+                "+reviews"
+            ]
+        );
+        match &diff.tables()[2] {
+            TableChange::Altered(books) => match &books.columns()[0] {
+                Change::Changed(old, new) => {
+                    assert_eq!((old.kind(), new.kind()), ("TEXT", "INTEGER"));
+                }
+                change => panic!("Unexpected change {:?}", change),
+            },
+            change => panic!("Unexpected change {:?}", change),
+        }
+        assert_eq!(diff.views().len(), 1);
+        assert_eq!(diff.triggers().len(), 1);
+
+        let script = diff.sql();
+        assert!(script.starts_with(
+            "PRAGMA foreign_keys = OFF;\n\
+             BEGIN;\n\
+             DROP TRIGGER \"trg_old\";\n\
+             DROP VIEW \"cheap\";\n\
+             DROP TABLE \"shelves\";\n\
+             CREATE TABLE \"authors_new\" (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT '');\n\
+             INSERT INTO \"authors_new\" (\"id\", \"name\") SELECT \"id\", \"name\" FROM \"authors\";\n\
+             DROP TABLE \"authors\";\n"
+        ));
+        assert!(script.ends_with(
+            "CREATE VIEW cheap AS SELECT title, price FROM books WHERE price < 100;\n\
+             CREATE TRIGGER trg_books AFTER DELETE ON books BEGIN DELETE FROM shelves; END;\n\
+             PRAGMA foreign_key_check;\n\
+             COMMIT;\n\
+             PRAGMA foreign_keys = ON;"
+        ));
+
+        conn.execute(&script)?;
+        assert!(Schema::read(&conn)?.diff(&to).is_empty());
+        assert_eq!(Schema::read(&conn)?.diff(&to).sql(), "");
+
+        let price = Sqlite3Builder::select_from("books")
+            .field("price")
+            .get_as::<(sqlite3::Value,)>(&conn)?;
+        assert_eq!(price, vec![(sqlite3::Value::Integer(120),)]);
+        let name = Sqlite3Builder::select_from("authors")
+            .field("name")
+            .get_string(&conn)?;
+        assert_eq!(name, "Herbert");
+
+        conn.execute(
+            "PRAGMA foreign_keys = OFF; \
+             CREATE TABLE \"book \"\"tags\"\"\" (book INTEGER REFERENCES books, tag TEXT); \
+             INSERT INTO \"book \"\"tags\"\"\" VALUES (2, 'sf'); \
+             PRAGMA foreign_keys = ON;",
+        )?;
+        sqlite3::open(&stage)?.execute(
+            "CREATE TABLE \"book \"\"tags\"\"\" (book INTEGER REFERENCES books, tag TEXT NOT NULL);",
+        )?;
+        let to = Schema::read_file(&stage)?;
+        let diff = Schema::read(&conn)?.diff(&to);
+        assert!(diff.sql().contains(
+            "\nALTER TABLE \"book \"\"tags\"\"_new\" RENAME TO \"book \"\"tags\"\"\";\n"
+        ));
+        let err = diff.exec(&conn).unwrap_err();
+        assert!(err.is_constraint());
+        assert_eq!(Schema::read(&conn)?.diff(&to), diff);
+        assert!(!Sqlite3Builder::select_from("pragma_legacy_alter_table")
+            .field("legacy_alter_table")
+            .get_bool(&conn)?);
+        assert!(Sqlite3Builder::select_from("pragma_foreign_keys")
+            .field("foreign_keys")
+            .get_bool(&conn)?);
+
+        std::fs::remove_file(&prod).unwrap();
+        std::fs::remove_file(&stage).unwrap();
+        let err = Schema::read_file(&prod).unwrap_err();
+        assert!(err.to_string().starts_with("No such database file"));
+
+        Ok(())
+    }
+}
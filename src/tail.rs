@@ -0,0 +1,63 @@
+//! Clauses ending SELECT: unions, ORDER BY, LIMIT and OFFSET.
+
+use std::fmt;
+
+/// Tail of SELECT which follows WHERE, GROUP BY and WINDOW clauses.
+/// ORDER BY is omitted with unions, it must be in the last subquery.
+#[derive(Clone, Debug, Default)]
+pub struct QueryTail {
+    unions: String,
+    order_by: Vec<String>,
+    limit: Option<String>,
+    offset: Option<String>,
+}
+
+impl QueryTail {
+    /// Add UNION or UNION ALL with query
+    pub fn union(&mut self, operator: &str, query: String) {
+        self.unions.push_str(&format!(" {} {}", operator, query));
+    }
+
+    /// Add ORDER BY expression
+    pub fn order_by(&mut self, field: String, desc: bool) {
+        self.order_by.push(if desc {
+            format!("{} DESC", field)
+        } else {
+            field
+        });
+    }
+
+    /// Set LIMIT
+    pub fn limit(&mut self, limit: String) {
+        self.limit = Some(limit);
+    }
+
+    /// Set OFFSET
+    pub fn offset(&mut self, offset: String) {
+        self.offset = Some(offset);
+    }
+
+    /// Whether no clause is set
+    pub fn is_empty(&self) -> bool {
+        self.unions.is_empty()
+            && self.order_by.is_empty()
+            && self.limit.is_none()
+            && self.offset.is_none()
+    }
+}
+
+impl fmt::Display for QueryTail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.unions)?;
+        if !self.order_by.is_empty() && self.unions.is_empty() {
+            write!(f, " ORDER BY {}", self.order_by.join(", "))?;
+        }
+        if let Some(limit) = &self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+        if let Some(offset) = &self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,446 @@
+//! Transactions and savepoints.
+
+use crate::error::Error;
+use crate::executor::Executor;
+use sqlite3::Connection;
+use std::cell::Cell;
+use std::ops::Deref;
+
+/// Locking behavior of transaction
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Behavior {
+    /// Acquire locks on first read or write
+    #[default]
+    Deferred,
+    /// Acquire write lock immediately
+    Immediate,
+    /// Acquire exclusive lock immediately
+    Exclusive,
+}
+
+impl Behavior {
+    /// SQL command starting transaction
+    fn begin(self) -> &'static str {
+        match self {
+            Behavior::Deferred => "BEGIN DEFERRED;",
+            Behavior::Immediate => "BEGIN IMMEDIATE;",
+            Behavior::Exclusive => "BEGIN EXCLUSIVE;",
+        }
+    }
+}
+
+/// Transaction guard.
+/// Rolls back on drop unless committed.
+/// May be passed to `exec` and `get*` methods as connection.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::{Sqlite3Builder, Transaction};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+/// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+/// let conn = pool.get()?;
+/// # conn.execute("CREATE TABLE books (title TEXT, price INTEGER);")?;
+///
+/// let tx = Transaction::new(&conn)?;
+///
+/// Sqlite3Builder::insert_into("books")
+///     .field("title")
+///     .field("price")
+///     .values(&["'Dune'", "120"])
+///     .exec(&tx)?;
+///
+/// Sqlite3Builder::update_table("books")
+///     .set("price", "price * 2")
+///     .exec(&tx)?;
+///
+/// tx.commit()?;
+///
+/// let price = Sqlite3Builder::select_from("books")
+///     .field("price")
+///     .get_int(&conn)?;
+///
+/// assert_eq!(240, price);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Transaction<'a> {
+    conn: &'a Connection,
+    savepoints: Cell<usize>,
+    finished: bool,
+}
+
+impl<'a> Transaction<'a> {
+    /// Begin deferred transaction
+    pub fn new(conn: &'a impl Executor) -> Result<Self, Error> {
+        Self::with_behavior(conn, Behavior::Deferred)
+    }
+
+    /// Begin transaction with chosen locking behavior
+    pub fn with_behavior(conn: &'a impl Executor, behavior: Behavior) -> Result<Self, Error> {
+        let conn = conn.connection();
+        let sql = behavior.begin();
+        debug!("Transaction sql = {}", sql);
+        conn.execute(sql)?;
+        Ok(Self {
+            conn,
+            savepoints: Cell::new(0),
+            finished: false,
+        })
+    }
+
+    /// Start nested unit of work with auto-generated savepoint name
+    pub fn savepoint(&self) -> Result<Savepoint<'_>, Error> {
+        Savepoint::new(self.conn, &self.savepoints)
+    }
+
+    /// Run closure in savepoint.
+    /// Releases savepoint if closure succeeds and rolls back to it if closure fails.
+    ///
+    /// ```
+    /// extern crate sqlite3builder;
+    ///
+    /// # use std::error::Error;
+    /// use sqlite3builder::{transaction, Sqlite3Builder};
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+    /// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+    /// let conn = pool.get()?;
+    /// # conn.execute("CREATE TABLE books (title TEXT UNIQUE);")?;
+    ///
+    /// transaction(&conn, |tx| -> Result<(), sqlite3builder::Error> {
+    ///     for title in &["Dune", "Emma", "Dune"] {
+    ///         let res = tx.with_savepoint(|sp| {
+    ///             let mut db = Sqlite3Builder::insert_into("books");
+    ///             let title = db.param(*title);
+    ///             db.field("title").values(&[title]).exec(sp)
+    ///         });
+    ///         if let Err(err) = res {
+    ///             if !err.is_constraint() {
+    ///                 return Err(err);
+    ///             }
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// })?;
+    ///
+    /// let count = Sqlite3Builder::select_from("books")
+    ///     .field("COUNT(*)")
+    ///     .get_int(&conn)?;
+    ///
+    /// assert_eq!(2, count);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_savepoint<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        E: From<Error>,
+        F: FnOnce(&Savepoint) -> Result<T, E>,
+    {
+        self.savepoint()?.run(f)
+    }
+
+    /// Commit transaction
+    pub fn commit(mut self) -> Result<(), Error> {
+        debug!("Transaction sql = COMMIT;");
+        self.conn.execute("COMMIT;")?;
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Roll back transaction
+    pub fn rollback(mut self) -> Result<(), Error> {
+        self.finished = true;
+        debug!("Transaction sql = ROLLBACK;");
+        self.conn.execute("ROLLBACK;")?;
+        Ok(())
+    }
+}
+
+impl<'a> Deref for Transaction<'a> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+impl<'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            debug!("Transaction sql = ROLLBACK;");
+            if let Err(err) = self.conn.execute("ROLLBACK;") {
+                error!("Transaction rollback failed: {}", err);
+            }
+        }
+    }
+}
+
+/// Savepoint guard for nested unit of work.
+/// Rolls back to savepoint on drop unless released.
+/// May be passed to `exec` and `get*` methods as connection.
+pub struct Savepoint<'a> {
+    conn: &'a Connection,
+    name: String,
+    savepoints: &'a Cell<usize>,
+    finished: bool,
+}
+
+impl<'a> Savepoint<'a> {
+    /// Create savepoint with next name of transaction
+    fn new(conn: &'a Connection, savepoints: &'a Cell<usize>) -> Result<Self, Error> {
+        savepoints.set(savepoints.get() + 1);
+        let name = format!("sp_{}", savepoints.get());
+        let sql = format!("SAVEPOINT {};", &name);
+        debug!("Savepoint sql = {}", &sql);
+        conn.execute(&sql)?;
+        Ok(Self {
+            conn,
+            name,
+            savepoints,
+            finished: false,
+        })
+    }
+
+    /// Savepoint name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Start nested unit of work inside this one
+    pub fn savepoint(&self) -> Result<Savepoint<'_>, Error> {
+        Savepoint::new(self.conn, self.savepoints)
+    }
+
+    /// Run closure in nested savepoint.
+    /// Releases savepoint if closure succeeds and rolls back to it if closure fails.
+    pub fn with_savepoint<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        E: From<Error>,
+        F: FnOnce(&Savepoint) -> Result<T, E>,
+    {
+        self.savepoint()?.run(f)
+    }
+
+    /// Release savepoint keeping its changes in the enclosing transaction
+    pub fn release(mut self) -> Result<(), Error> {
+        let sql = format!("RELEASE {};", &self.name);
+        debug!("Savepoint sql = {}", &sql);
+        self.conn.execute(&sql)?;
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Discard changes made since savepoint
+    pub fn rollback(mut self) -> Result<(), Error> {
+        self.finished = true;
+        self.rollback_to()
+    }
+
+    /// Roll back to savepoint and remove it
+    fn rollback_to(&self) -> Result<(), Error> {
+        let sql = format!("ROLLBACK TO {name}; RELEASE {name};", name = &self.name);
+        debug!("Savepoint sql = {}", &sql);
+        self.conn.execute(&sql)?;
+        Ok(())
+    }
+
+    /// Run closure and release savepoint if it succeeds
+    fn run<T, E, F>(self, f: F) -> Result<T, E>
+    where
+        E: From<Error>,
+        F: FnOnce(&Savepoint) -> Result<T, E>,
+    {
+        let result = f(&self)?;
+        self.release()?;
+        Ok(result)
+    }
+}
+
+impl<'a> Deref for Savepoint<'a> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+impl<'a> Drop for Savepoint<'a> {
+    fn drop(&mut self) {
+        if !self.finished {
+            if let Err(err) = self.rollback_to() {
+                error!("Savepoint rollback failed: {}", err);
+            }
+        }
+    }
+}
+
+/// Run closure in deferred transaction.
+/// Commits if closure succeeds and rolls back if it fails.
+/// Closure may fail with any error convertible from crate `Error`.
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// # use std::error::Error;
+/// use sqlite3builder::{transaction, Sqlite3Builder};
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+/// # let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+/// let conn = pool.get()?;
+/// # conn.execute("CREATE TABLE books (title TEXT, price INTEGER);")?;
+///
+/// let count = transaction(&conn, |tx| {
+///     Sqlite3Builder::insert_into("books")
+///         .field("title")
+///         .values(&["'Dune'"])
+///         .exec(tx)?;
+///
+///     Sqlite3Builder::select_from("books")
+///         .field("COUNT(*)")
+///         .get_int(tx)
+/// })?;
+///
+/// assert_eq!(1, count);
+/// # Ok(())
+/// # }
+/// ```
+pub fn transaction<T, E, F>(conn: &impl Executor, f: F) -> Result<T, E>
+where
+    E: From<Error>,
+    F: FnOnce(&Transaction) -> Result<T, E>,
+{
+    transaction_with(conn, Behavior::Deferred, f)
+}
+
+/// Run closure in transaction with chosen locking behavior.
+/// Commits if closure succeeds and rolls back if it fails.
+pub fn transaction_with<T, E, F>(conn: &impl Executor, behavior: Behavior, f: F) -> Result<T, E>
+where
+    E: From<Error>,
+    F: FnOnce(&Transaction) -> Result<T, E>,
+{
+    let tx = Transaction::with_behavior(conn, behavior)?;
+    let result = f(&tx)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sqlite3Builder;
+    use std::error::Error;
+
+    fn books() -> Result<r2d2::Pool<r2d2_sqlite3::SqliteConnectionManager>, Box<dyn Error>> {
+        let manager = r2d2_sqlite3::SqliteConnectionManager::memory();
+        let pool = r2d2::Pool::builder().max_size(1).build(manager)?;
+        pool.get()?
+            .execute("CREATE TABLE books (title TEXT, price INTEGER);")?;
+        Ok(pool)
+    }
+
+    fn count(conn: &impl Executor) -> Result<i64, crate::Error> {
+        Sqlite3Builder::select_from("books")
+            .field("COUNT(*)")
+            .get_int(conn)
+    }
+
+    fn add_book(conn: &impl Executor) -> Result<(), crate::Error> {
+        Sqlite3Builder::insert_into("books")
+            .field("title")
+            .values(&["'Dune'"])
+            .exec(conn)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollback_on_drop() -> Result<(), Box<dyn Error>> {
+        let pool = books()?;
+        let conn = pool.get()?;
+
+        {
+            let tx = Transaction::with_behavior(&conn, Behavior::Immediate)?;
+            add_book(&tx)?;
+            assert_eq!(count(&tx)?, 1);
+        }
+
+        assert_eq!(count(&conn)?, 0);
+
+        let tx = Transaction::with_behavior(&conn, Behavior::Exclusive)?;
+        add_book(&tx)?;
+        tx.rollback()?;
+
+        assert_eq!(count(&conn)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transaction_closure() -> Result<(), Box<dyn Error>> {
+        let pool = books()?;
+        let conn = pool.get()?;
+
+        let res: Result<(), Box<dyn Error>> = transaction(&conn, |tx| {
+            add_book(tx)?;
+            Err("Import failed".into())
+        });
+
+        assert!(res.is_err());
+        assert_eq!(count(&conn)?, 0);
+
+        transaction_with(&conn, Behavior::Immediate, |tx| {
+            add_book(tx)?;
+            add_book(tx)
+        })?;
+
+        assert_eq!(count(&conn)?, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_savepoints() -> Result<(), Box<dyn Error>> {
+        let pool = books()?;
+        let conn = pool.get()?;
+
+        let tx = Transaction::new(&conn)?;
+        add_book(&tx)?;
+
+        tx.with_savepoint(|outer| -> Result<(), Box<dyn Error>> {
+            assert_eq!(outer.name(), "sp_1");
+            add_book(outer)?;
+
+            let res: Result<(), Box<dyn Error>> = outer.with_savepoint(|inner| {
+                assert_eq!(inner.name(), "sp_2");
+                add_book(inner)?;
+                Err("Bad record".into())
+            });
+
+            assert!(res.is_err());
+            assert_eq!(count(outer)?, 2);
+
+            let inner = outer.savepoint()?;
+            add_book(&inner)?;
+            inner.release()?;
+
+            Ok(())
+        })?;
+
+        {
+            let sp = tx.savepoint()?;
+            add_book(&sp)?;
+        }
+
+        tx.commit()?;
+
+        assert_eq!(count(&conn)?, 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+//! ON CONFLICT clauses of INSERT.
+
+use crate::error::Error;
+
+/// Action taken on conflict
+#[derive(Clone, Debug, PartialEq)]
+enum Action {
+    /// Not chosen yet
+    Unset,
+    /// DO NOTHING
+    Nothing,
+    /// DO UPDATE SET
+    Update,
+}
+
+/// Single ON CONFLICT clause
+#[derive(Clone, Debug)]
+pub struct OnConflict {
+    target: Vec<String>,
+    target_wheres: Vec<String>,
+    action: Action,
+    sets: Vec<String>,
+    update_all: bool,
+    update_wheres: Vec<String>,
+}
+
+impl OnConflict {
+    /// Create clause with conflict target columns
+    pub fn new(target: Vec<String>) -> Self {
+        Self {
+            target,
+            target_wheres: Vec::new(),
+            action: Action::Unset,
+            sets: Vec::new(),
+            update_all: false,
+            update_wheres: Vec::new(),
+        }
+    }
+
+    /// Add condition of partial index
+    pub fn target_where(&mut self, cond: String) {
+        self.target_wheres.push(cond);
+    }
+
+    /// Do nothing on conflict
+    pub fn do_nothing(&mut self) {
+        self.action = Action::Nothing;
+    }
+
+    /// Add `field = value` to update
+    pub fn set(&mut self, field: String, value: String) {
+        self.action = Action::Update;
+        self.sets.push(format!("{} = {}", field, value));
+    }
+
+    /// Update all inserted fields except conflict target
+    pub fn update_all(&mut self) {
+        self.action = Action::Update;
+        self.update_all = true;
+    }
+
+    /// Add condition of update
+    pub fn update_where(&mut self, cond: String) {
+        self.update_wheres.push(cond);
+    }
+
+    /// Build clause for inserted fields
+    pub fn sql(&self, fields: &[String]) -> Result<String, Error> {
+        let mut text = "ON CONFLICT".to_string();
+        if !self.target.is_empty() {
+            text.push_str(&format!(" ({})", self.target.join(", ")));
+            if !self.target_wheres.is_empty() {
+                text.push_str(" WHERE ");
+                text.push_str(&self.target_wheres.join(" AND "));
+            }
+        } else if !self.target_wheres.is_empty() {
+            return Err(Error::Build(
+                "ON CONFLICT WHERE requires conflict target".to_string(),
+            ));
+        }
+
+        match self.action {
+            Action::Unset => Err(Error::Build("No ON CONFLICT action".to_string())),
+            Action::Nothing => {
+                text.push_str(" DO NOTHING");
+                Ok(text)
+            }
+            Action::Update => {
+                if self.target.is_empty() {
+                    return Err(Error::Build(
+                        "ON CONFLICT DO UPDATE requires conflict target".to_string(),
+                    ));
+                }
+                let mut sets = Vec::new();
+                if self.update_all {
+                    sets.extend(
+                        fields
+                            .iter()
+                            .filter(|field| !self.target.contains(field))
+                            .map(|field| format!("{} = excluded.{}", field, field)),
+                    );
+                }
+                sets.extend(self.sets.iter().cloned());
+                if sets.is_empty() {
+                    return Err(Error::Build("No fields to update on conflict".to_string()));
+                }
+                text.push_str(" DO UPDATE SET ");
+                text.push_str(&sets.join(", "));
+                if !self.update_wheres.is_empty() {
+                    text.push_str(" WHERE ");
+                    text.push_str(&self.update_wheres.join(" AND "));
+                }
+                Ok(text)
+            }
+        }
+    }
+}
+
+/// Whether INSERT ends with SELECT from tables without WHERE clause,
+/// so ON CONFLICT would be parsed as join constraint
+pub fn select_needs_where(insert: &str) -> bool {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0usize;
+    let mut chars = insert.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            if depth == 0 {
+                word.push(c.to_ascii_uppercase());
+            }
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        let close = match c {
+            '(' => {
+                depth += 1;
+                None
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                None
+            }
+            '\'' | '"' | '`' => Some(c),
+            '[' => Some(']'),
+            '-' if chars.peek() == Some(&'-') => Some('\n'),
+            _ => None,
+        };
+        if let Some(close) = close {
+            for c in chars.by_ref() {
+                if c == close {
+                    break;
+                }
+            }
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    let last = words
+        .iter()
+        .rposition(|word| ["UNION", "INTERSECT", "EXCEPT"].contains(&word.as_str()))
+        .map_or(0, |pos| pos + 1);
+    let select = &words[last..];
+    select.iter().any(|word| word == "FROM")
+        && !select
+            .iter()
+            .any(|word| ["WHERE", "GROUP", "ORDER", "LIMIT", "WINDOW"].contains(&word.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(src: &[&str]) -> Vec<String> {
+        src.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_on_conflict_sql() -> Result<(), Error> {
+        let fields = strings(&["isbn", "title", "price"]);
+
+        let mut clause = OnConflict::new(strings(&["isbn"]));
+        clause.update_all();
+        clause.set("updated".to_string(), "CURRENT_TIMESTAMP".to_string());
+        clause.update_where("excluded.price > 0".to_string());
+        assert_eq!(
+            clause.sql(&fields)?,
+            "ON CONFLICT (isbn) DO UPDATE SET title = excluded.title, price = excluded.price, \
+             updated = CURRENT_TIMESTAMP WHERE excluded.price > 0"
+        );
+
+        let mut clause = OnConflict::new(Vec::new());
+        clause.do_nothing();
+        assert_eq!(clause.sql(&fields)?, "ON CONFLICT DO NOTHING");
+
+        clause.update_all();
+        assert!(clause.sql(&fields).is_err());
+
+        let mut clause = OnConflict::new(fields.clone());
+        assert!(clause.sql(&fields).is_err());
+        clause.update_all();
+        assert!(clause.sql(&fields).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_needs_where() {
+        assert!(!select_needs_where("INSERT INTO t (a) VALUES (1)"));
+        assert!(!select_needs_where("INSERT INTO t (a) SELECT 1"));
+        assert!(select_needs_where("INSERT INTO t (a) SELECT a FROM s"));
+        assert!(!select_needs_where(
+            "INSERT INTO t (a) SELECT a FROM s WHERE a > 0"
+        ));
+        assert!(!select_needs_where(
+            "INSERT INTO t (a) SELECT a FROM s ORDER BY a"
+        ));
+        assert!(!select_needs_where(
+            "INSERT INTO t (a) SELECT a FROM (SELECT a FROM u WHERE a) AS s WHERE a"
+        ));
+        assert!(select_needs_where(
+            "INSERT INTO t (a) SELECT a FROM s WHERE a UNION SELECT a FROM u"
+        ));
+        assert!(select_needs_where(
+            "INSERT INTO t (a) SELECT 'where' FROM \"order\""
+        ));
+    }
+}
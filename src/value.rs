@@ -0,0 +1,375 @@
+//! Conversion between Rust values and Sqlite3 values.
+
+use crate::error::Error;
+use sqlite3::Value as SValue;
+use std::convert::TryFrom;
+
+/// Value which may be bound to a statement parameter
+pub trait ToValue {
+    /// Convert to sqlite3::Value
+    fn to_value(&self) -> SValue;
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self) -> SValue {
+        (**self).to_value()
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> SValue {
+        match self {
+            Some(val) => val.to_value(),
+            None => SValue::Null,
+        }
+    }
+}
+
+impl ToValue for SValue {
+    fn to_value(&self) -> SValue {
+        self.clone()
+    }
+}
+
+macro_rules! integer_to_value {
+    ($($t:ty),*) => {
+        $(
+            impl ToValue for $t {
+                fn to_value(&self) -> SValue {
+                    SValue::Integer(i64::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+integer_to_value!(i8, i16, i32, i64, u8, u16, u32);
+
+impl ToValue for bool {
+    fn to_value(&self) -> SValue {
+        SValue::Integer(i64::from(*self))
+    }
+}
+
+impl ToValue for f32 {
+    fn to_value(&self) -> SValue {
+        SValue::Float(f64::from(*self))
+    }
+}
+
+impl ToValue for f64 {
+    fn to_value(&self) -> SValue {
+        SValue::Float(*self)
+    }
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> SValue {
+        SValue::String(self.to_string())
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> SValue {
+        SValue::String(self.clone())
+    }
+}
+
+impl ToValue for [u8] {
+    fn to_value(&self) -> SValue {
+        SValue::Binary(self.to_vec())
+    }
+}
+
+impl ToValue for Vec<u8> {
+    fn to_value(&self) -> SValue {
+        SValue::Binary(self.clone())
+    }
+}
+
+/// Value which may be read from a result column
+pub trait FromValue: Sized {
+    /// Convert from sqlite3::Value
+    fn from_value(value: &SValue) -> Result<Self, Error>;
+
+    /// Convert from sqlite3::Value of other storage class following SQLite affinity rules.
+    /// Well-formed numeric text becomes a number, exact REAL becomes INTEGER,
+    /// numbers become text and text becomes BLOB.
+    fn from_value_coerced(value: &SValue) -> Result<Self, Error> {
+        Self::from_value(value)
+    }
+}
+
+/// Error for unexpected value type
+fn mismatch(expected: &str, value: &SValue) -> Error {
+    Error::conversion(format!("Expected {}, found {:?}", expected, value.kind()))
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &SValue) -> Result<Self, Error> {
+        match value {
+            SValue::Null => Ok(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+
+    fn from_value_coerced(value: &SValue) -> Result<Self, Error> {
+        match value {
+            SValue::Null => Ok(None),
+            _ => T::from_value_coerced(value).map(Some),
+        }
+    }
+}
+
+impl FromValue for SValue {
+    fn from_value(value: &SValue) -> Result<Self, Error> {
+        Ok(value.clone())
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &SValue) -> Result<Self, Error> {
+        match value {
+            SValue::Integer(val) => Ok(*val),
+            _ => Err(mismatch("Integer", value)),
+        }
+    }
+
+    fn from_value_coerced(value: &SValue) -> Result<Self, Error> {
+        let exact = |val: f64| {
+            if val.fract() == 0.0 && val >= i64::MIN as f64 && val < i64::MAX as f64 {
+                Ok(val as i64)
+            } else {
+                Err(Error::conversion(format!(
+                    "Float {} is not an exact integer",
+                    val
+                )))
+            }
+        };
+        match value {
+            SValue::Float(val) => exact(*val),
+            SValue::String(text) => match text.trim().parse::<i64>() {
+                Ok(val) => Ok(val),
+                Err(_) => exact(parse_float(text)?),
+            },
+            _ => Self::from_value(value),
+        }
+    }
+}
+
+/// Convert integer to narrower type
+fn narrow<T: TryFrom<i64>>(val: i64) -> Result<T, Error> {
+    T::try_from(val).map_err(|_| {
+        Error::conversion(format!(
+            "Integer {} out of range for {}",
+            val,
+            std::any::type_name::<T>()
+        ))
+    })
+}
+
+/// Parse well-formed real literal
+fn parse_float(text: &str) -> Result<f64, Error> {
+    match text.trim().parse::<f64>() {
+        Ok(val) if val.is_finite() => Ok(val),
+        _ => Err(Error::conversion(format!(
+            "Text {:?} is not a number",
+            text
+        ))),
+    }
+}
+
+macro_rules! integer_from_value {
+    ($($t:ty),*) => {
+        $(
+            impl FromValue for $t {
+                fn from_value(value: &SValue) -> Result<Self, Error> {
+                    narrow(i64::from_value(value)?)
+                }
+
+                fn from_value_coerced(value: &SValue) -> Result<Self, Error> {
+                    narrow(i64::from_value_coerced(value)?)
+                }
+            }
+        )*
+    };
+}
+
+integer_from_value!(i8, i16, i32, u8, u16, u32, u64, usize);
+
+impl FromValue for bool {
+    fn from_value(value: &SValue) -> Result<Self, Error> {
+        Ok(i64::from_value(value)? != 0)
+    }
+
+    fn from_value_coerced(value: &SValue) -> Result<Self, Error> {
+        Ok(i64::from_value_coerced(value)? != 0)
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &SValue) -> Result<Self, Error> {
+        match value {
+            SValue::Float(val) => Ok(*val),
+            SValue::Integer(val) => Ok(*val as f64),
+            _ => Err(mismatch("Float", value)),
+        }
+    }
+
+    fn from_value_coerced(value: &SValue) -> Result<Self, Error> {
+        match value {
+            SValue::String(text) => parse_float(text),
+            _ => Self::from_value(value),
+        }
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: &SValue) -> Result<Self, Error> {
+        Ok(f64::from_value(value)? as f32)
+    }
+
+    fn from_value_coerced(value: &SValue) -> Result<Self, Error> {
+        Ok(f64::from_value_coerced(value)? as f32)
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &SValue) -> Result<Self, Error> {
+        match value {
+            SValue::String(val) => Ok(val.clone()),
+            _ => Err(mismatch("String", value)),
+        }
+    }
+
+    fn from_value_coerced(value: &SValue) -> Result<Self, Error> {
+        match value {
+            SValue::Integer(val) => Ok(val.to_string()),
+            SValue::Float(val) => Ok(real_to_text(*val)),
+            _ => Self::from_value(value),
+        }
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: &SValue) -> Result<Self, Error> {
+        match value {
+            SValue::Binary(val) => Ok(val.clone()),
+            _ => Err(mismatch("Binary", value)),
+        }
+    }
+
+    fn from_value_coerced(value: &SValue) -> Result<Self, Error> {
+        match value {
+            SValue::String(val) => Ok(val.clone().into_bytes()),
+            _ => Self::from_value(value),
+        }
+    }
+}
+
+/// Text of REAL as SQLite casts it: 15 significant digits with decimal point or exponent
+fn real_to_text(val: f64) -> String {
+    if val.is_nan() {
+        return "NaN".to_string();
+    }
+    if val.is_infinite() {
+        return if val > 0.0 { "Inf" } else { "-Inf" }.to_string();
+    }
+    if val == 0.0 {
+        return "0.0".to_string();
+    }
+    let sci = format!("{:.14e}", val);
+    let (mantissa, exp) = sci.split_at(sci.find('e').unwrap_or(sci.len()));
+    let exp = exp[1..].parse::<i32>().unwrap_or(0);
+    if !(-4..15).contains(&exp) {
+        let sign = if exp < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim_fraction(mantissa), sign, exp.abs())
+    } else {
+        trim_fraction(&format!("{:.*}", (14 - exp) as usize, val))
+    }
+}
+
+/// Remove trailing zeros of fraction, keeping at least one digit after decimal point
+fn trim_fraction(num: &str) -> String {
+    if !num.contains('.') {
+        return format!("{}.0", num);
+    }
+    let trimmed = num.trim_end_matches('0');
+    if trimmed.ends_with('.') {
+        format!("{}0", trimmed)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(val: &str) -> SValue {
+        SValue::String(val.to_string())
+    }
+
+    #[test]
+    fn test_from_value() {
+        assert_eq!(i64::from_value(&SValue::Integer(7)).unwrap(), 7);
+        assert_eq!(f64::from_value(&SValue::Integer(7)).unwrap(), 7.0);
+        assert!(i64::from_value(&text("7")).is_err());
+        assert!(u8::from_value(&SValue::Integer(300)).is_err());
+        assert!(String::from_value(&SValue::Null).is_err());
+        assert_eq!(Option::<String>::from_value(&SValue::Null).unwrap(), None);
+    }
+
+    #[test]
+    fn test_from_value_coerced() {
+        assert_eq!(i64::from_value_coerced(&text(" 42 ")).unwrap(), 42);
+        assert_eq!(i64::from_value_coerced(&text("1e3")).unwrap(), 1000);
+        assert_eq!(i64::from_value_coerced(&SValue::Float(3.0)).unwrap(), 3);
+        assert!(i64::from_value_coerced(&SValue::Float(3.5)).is_err());
+        assert!(i64::from_value_coerced(&text("42abc")).is_err());
+        assert!(f64::from_value_coerced(&text("inf")).is_err());
+        assert_eq!(f64::from_value_coerced(&text("2.5")).unwrap(), 2.5);
+        assert!(bool::from_value_coerced(&text("1")).unwrap());
+        assert_eq!(
+            String::from_value_coerced(&SValue::Float(2.5)).unwrap(),
+            "2.5"
+        );
+        assert_eq!(
+            String::from_value_coerced(&SValue::Float(3.0)).unwrap(),
+            "3.0"
+        );
+        assert_eq!(Vec::<u8>::from_value_coerced(&text("ab")).unwrap(), b"ab");
+        assert_eq!(
+            Option::<i64>::from_value_coerced(&SValue::Null).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_real_to_text() -> Result<(), Error> {
+        let conn = sqlite3::open(":memory:")?;
+        for expr in &[
+            "3.0",
+            "-2.5",
+            "0.1",
+            "1.0 / 3",
+            "100.0",
+            "0.000123",
+            "1.5e-7",
+            "1e14",
+            "1e15",
+            "1e20",
+            "123456789012345678.0",
+            "9.9999999999999999e14",
+            "-0.0",
+            "1e300 * 1e300",
+            "-1e300 * 1e300",
+        ] {
+            let mut stmt = conn.prepare(format!("SELECT {0}, CAST({0} AS TEXT);", expr))?;
+            stmt.next()?;
+            let real = stmt.read::<f64>(0)?;
+            assert_eq!(real_to_text(real), stmt.read::<String>(1)?, "{}", expr);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+//! Window functions and window definitions.
+
+use std::fmt;
+
+/// Bound of window frame
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrameBound {
+    /// UNBOUNDED PRECEDING
+    UnboundedPreceding,
+    /// N PRECEDING
+    Preceding(u64),
+    /// CURRENT ROW
+    CurrentRow,
+    /// N FOLLOWING
+    Following(u64),
+    /// UNBOUNDED FOLLOWING
+    UnboundedFollowing,
+}
+
+impl fmt::Display for FrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameBound::UnboundedPreceding => write!(f, "UNBOUNDED PRECEDING"),
+            FrameBound::Preceding(n) => write!(f, "{} PRECEDING", n),
+            FrameBound::CurrentRow => write!(f, "CURRENT ROW"),
+            FrameBound::Following(n) => write!(f, "{} FOLLOWING", n),
+            FrameBound::UnboundedFollowing => write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
+}
+
+/// Window definition: `(base PARTITION BY ... ORDER BY ... frame)`
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// use sqlite3builder::{FrameBound, Window};
+///
+/// let window = Window::new()
+///     .partition_by("author")
+///     .order_asc("published")
+///     .rows_between(FrameBound::Preceding(2), FrameBound::CurrentRow)
+///     .to_string();
+///
+/// assert_eq!("(PARTITION BY author ORDER BY published ROWS BETWEEN 2 PRECEDING AND CURRENT ROW)", &window);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Window {
+    base: Option<String>,
+    partition_by: Vec<String>,
+    order_by: Vec<String>,
+    frame: Option<String>,
+}
+
+impl Window {
+    /// Create empty window
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create window based on named one.
+    /// Base window must not have frame.
+    pub fn based_on<S: ToString>(name: S) -> Self {
+        Self {
+            base: Some(name.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Add PARTITION BY expression
+    pub fn partition_by<S: ToString>(&mut self, expr: S) -> &mut Self {
+        self.partition_by.push(expr.to_string());
+        self
+    }
+
+    /// Add ORDER BY expression
+    pub fn order_by<S: ToString>(&mut self, expr: S, desc: bool) -> &mut Self {
+        let order = if desc {
+            format!("{} DESC", expr.to_string())
+        } else {
+            expr.to_string()
+        };
+        self.order_by.push(order);
+        self
+    }
+
+    /// Add ascending ORDER BY expression
+    pub fn order_asc<S: ToString>(&mut self, expr: S) -> &mut Self {
+        self.order_by(expr, false)
+    }
+
+    /// Add descending ORDER BY expression
+    pub fn order_desc<S: ToString>(&mut self, expr: S) -> &mut Self {
+        self.order_by(expr, true)
+    }
+
+    /// Set frame in rows
+    pub fn rows_between(&mut self, start: FrameBound, end: FrameBound) -> &mut Self {
+        self.frame("ROWS", start, end)
+    }
+
+    /// Set frame in ranges of ORDER BY values
+    pub fn range_between(&mut self, start: FrameBound, end: FrameBound) -> &mut Self {
+        self.frame("RANGE", start, end)
+    }
+
+    /// Set frame in groups of peer rows
+    pub fn groups_between(&mut self, start: FrameBound, end: FrameBound) -> &mut Self {
+        self.frame("GROUPS", start, end)
+    }
+
+    fn frame(&mut self, units: &str, start: FrameBound, end: FrameBound) -> &mut Self {
+        self.frame = Some(format!("{} BETWEEN {} AND {}", units, start, end));
+        self
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(base) = &self.base {
+            parts.push(base.clone());
+        }
+        if !self.partition_by.is_empty() {
+            parts.push(format!("PARTITION BY {}", self.partition_by.join(", ")));
+        }
+        if !self.order_by.is_empty() {
+            parts.push(format!("ORDER BY {}", self.order_by.join(", ")));
+        }
+        if let Some(frame) = &self.frame {
+            parts.push(frame.clone());
+        }
+        write!(f, "({})", parts.join(" "))
+    }
+}
+
+/// Window function call: `func(args) FILTER (WHERE ...) OVER window AS alias`
+///
+/// ```
+/// extern crate sqlite3builder;
+///
+/// use sqlite3builder::{aggregate, row_number, Window};
+///
+/// let by_author = Window::new().partition_by("author").order_desc("price").clone();
+///
+/// let pos = row_number().over(&by_author).alias("pos").to_string();
+/// assert_eq!("row_number() OVER (PARTITION BY author ORDER BY price DESC) AS pos", &pos);
+///
+/// let total = aggregate("SUM(price)").filter("price > 0").over_named("w").to_string();
+/// assert_eq!("SUM(price) FILTER (WHERE price > 0) OVER w", &total);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WindowFunction {
+    call: String,
+    filters: Vec<String>,
+    over: Option<String>,
+    alias: Option<String>,
+}
+
+impl WindowFunction {
+    /// Add FILTER condition, for aggregate functions only
+    pub fn filter<S: ToString>(&mut self, cond: S) -> &mut Self {
+        self.filters.push(cond.to_string());
+        self
+    }
+
+    /// Set window definition
+    pub fn over(&mut self, window: &Window) -> &mut Self {
+        self.over = Some(window.to_string());
+        self
+    }
+
+    /// Use window named in WINDOW clause
+    pub fn over_named<S: ToString>(&mut self, name: S) -> &mut Self {
+        self.over = Some(name.to_string());
+        self
+    }
+
+    /// Set result column name
+    pub fn alias<S: ToString>(&mut self, name: S) -> &mut Self {
+        self.alias = Some(name.to_string());
+        self
+    }
+}
+
+impl fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.call)?;
+        if !self.filters.is_empty() {
+            let cond = if self.filters.len() == 1 {
+                self.filters[0].clone()
+            } else {
+                format!("({})", self.filters.join(") AND ("))
+            };
+            write!(f, " FILTER (WHERE {})", cond)?;
+        }
+        match &self.over {
+            Some(over) => write!(f, " OVER {}", over)?,
+            None => write!(f, " OVER ()")?,
+        }
+        if let Some(alias) = &self.alias {
+            write!(f, " AS {}", alias)?;
+        }
+        Ok(())
+    }
+}
+
+/// Window call of any function, usually aggregate: `aggregate("SUM(price)")`
+pub fn aggregate<S: ToString>(call: S) -> WindowFunction {
+    WindowFunction {
+        call: call.to_string(),
+        filters: Vec::new(),
+        over: None,
+        alias: None,
+    }
+}
+
+/// Number of row in partition, starting from 1
+pub fn row_number() -> WindowFunction {
+    aggregate("row_number()")
+}
+
+/// Rank of row with gaps
+pub fn rank() -> WindowFunction {
+    aggregate("rank()")
+}
+
+/// Rank of row without gaps
+pub fn dense_rank() -> WindowFunction {
+    aggregate("dense_rank()")
+}
+
+/// Number of group when partition is divided into N groups
+pub fn ntile(groups: u64) -> WindowFunction {
+    aggregate(format!("ntile({})", groups))
+}
+
+/// Value of expression in row N rows before current one
+pub fn lag<S: ToString>(expr: S, offset: u64) -> WindowFunction {
+    aggregate(format!("lag({}, {})", expr.to_string(), offset))
+}
+
+/// Value of expression in row N rows after current one
+pub fn lead<S: ToString>(expr: S, offset: u64) -> WindowFunction {
+    aggregate(format!("lead({}, {})", expr.to_string(), offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_functions() {
+        let mut window = Window::based_on("w");
+        window.range_between(FrameBound::UnboundedPreceding, FrameBound::Following(1));
+        assert_eq!(
+            window.to_string(),
+            "(w RANGE BETWEEN UNBOUNDED PRECEDING AND 1 FOLLOWING)"
+        );
+
+        let window = Window::new()
+            .groups_between(FrameBound::CurrentRow, FrameBound::UnboundedFollowing)
+            .clone();
+        assert_eq!(
+            ntile(4).over(&window).to_string(),
+            "ntile(4) OVER (GROUPS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING)"
+        );
+
+        assert_eq!(rank().to_string(), "rank() OVER ()");
+        assert_eq!(
+            lag("price", 1).over_named("w").alias("prev").to_string(),
+            "lag(price, 1) OVER w AS prev"
+        );
+        assert_eq!(
+            aggregate("COUNT(*)")
+                .filter("price > 0")
+                .filter("author IS NOT NULL")
+                .to_string(),
+            "COUNT(*) FILTER (WHERE (price > 0) AND (author IS NOT NULL)) OVER ()"
+        );
+    }
+}